@@ -0,0 +1,116 @@
+//! Lookups in an `InternedMap` compared to a std `HashMap`.
+//!
+//! Uses the unstable `test` crate, so this needs a nightly toolchain and the
+//! `nightly` feature: `cargo +nightly bench --features nightly`.
+//!
+//! Interned maps win when the keys being looked up are already pooled strings
+//! (`lookup_by_string` and `count_tokens`). Looking up by `&str` has to go
+//! through the pool first, and is about as fast as std (`lookup_by_str`).
+
+#![feature(test)]
+
+extern crate test;
+
+use ::std::collections::HashMap;
+use ::std::string::String as StdString;
+use ::string_pool::String;
+use ::string_pool::collections::InternedMap;
+use ::test::{ Bencher, black_box };
+
+const KEYS: usize = 1000;
+
+fn keys() -> Vec<StdString> {
+	(0..KEYS)
+		.map(|i| format!("some reasonably long key, like an identifier or a path, number {i}"))
+		.collect()
+}
+
+#[bench]
+fn std_map_lookup(b: &mut Bencher) {
+	let keys = keys();
+	let map = keys.iter()
+		.enumerate()
+		.map(|(i, k)| (k.clone(), i))
+		.collect::<HashMap<_, _>>();
+
+	b.iter(|| {
+		for key in &keys {
+			black_box(map.get(black_box(&**key)));
+		}
+	});
+}
+
+#[bench]
+fn interned_map_lookup_by_string(b: &mut Bencher) {
+	let keys = keys().iter()
+		.map(|k| String::from(&**k))
+		.collect::<Vec<_>>();
+	let map = keys.iter()
+		.enumerate()
+		.map(|(i, k)| (k.clone(), i))
+		.collect::<InternedMap<_>>();
+
+	b.iter(|| {
+		for key in &keys {
+			black_box(map.get(black_box(key)));
+		}
+	});
+}
+
+#[bench]
+fn interned_map_lookup_by_str(b: &mut Bencher) {
+	let keys = keys();
+	let map = keys.iter()
+		.enumerate()
+		.map(|(i, k)| (String::from(&**k), i))
+		.collect::<InternedMap<_>>();
+
+	b.iter(|| {
+		for key in &keys {
+			black_box(map.get(black_box(&**key)));
+		}
+	});
+}
+
+/// Tokens of an input that was interned while parsing it, so most of them are
+/// repeats of a much smaller vocabulary
+fn tokens() -> Vec<StdString> {
+	let keys = keys();
+	(0..KEYS * 10)
+		.map(|i| keys[(i * 7919) % KEYS].clone())
+		.collect()
+}
+
+#[bench]
+fn std_map_count_tokens(b: &mut Bencher) {
+	let tokens = tokens();
+
+	b.iter(|| {
+		let mut counts = HashMap::<StdString, usize>::new();
+		for token in &tokens {
+			match counts.get_mut(&**token) {
+				Some(count) => { *count += 1 }
+				None => { counts.insert(token.clone(), 1); }
+			}
+		}
+		black_box(counts)
+	});
+}
+
+#[bench]
+fn interned_map_count_tokens(b: &mut Bencher) {
+	let tokens = tokens().iter()
+		.map(|t| String::from(&**t))
+		.collect::<Vec<_>>();
+
+	b.iter(|| {
+		let mut counts = InternedMap::<usize>::new();
+		for token in &tokens {
+			match counts.get_mut(token) {
+				Some(count) => { *count += 1 }
+				None => { counts.insert(token.clone(), 1); }
+			}
+		}
+		black_box(counts)
+	});
+}
@@ -0,0 +1,362 @@
+//! Generating Rust source for a static, perfect hashed table of strings, for
+//! example from a `build.rs` script.
+//!
+//! Every word gets its own named [`StaticString`] static, which interns itself
+//! into the [`GlobalPool`] the first time it's used, so it is pointer-equal to
+//! the same string interned at runtime. The table ([`StaticTable`]) finds the
+//! static for a `&str` with a perfect hash, without touching the pool.
+//!
+//! ```no_run
+//! // build.rs
+//! use string_pool::codegen::Codegen;
+//! use std::{ env, fs::File, path::Path };
+//!
+//! let path = Path::new(&env::var("OUT_DIR").unwrap()).join("keywords.rs");
+//! Codegen::new("KEYWORDS")
+//!     .prefix("KW_")
+//!     .words(["select", "from", "where"])
+//!     .write(File::create(path).unwrap())
+//!     .unwrap();
+//! ```
+//!
+//! ```ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/keywords.rs"));
+//!
+//! assert_eq!(*KW_SELECT, "select");
+//! assert!(KEYWORDS.get("from").is_some());
+//! ```
+
+use crate::pool::GlobalPool;
+use crate::string::String;
+use ::hashbrown::HashSet;
+use ::std::fmt::{ self, Debug };
+use ::std::io::{ self, Write };
+use ::std::ops::Deref;
+use ::std::sync::OnceLock;
+
+/// Average number of words per bucket
+const LAMBDA: usize = 5;
+
+/// Limits on how hard [`PerfectHash::generate`] tries: how many seeds, and how
+/// many values of each displacement per bucket (so up to this squared)
+const MAX_SEEDS: u64 = 32;
+const MAX_DISPLACEMENT: u32 = 1024;
+
+#[cfg(test)]
+#[path = "./tests/codegen.rs"]
+mod tests;
+
+/// A string known at compile time, interned into the [`GlobalPool`] the first
+/// time it's used
+pub struct StaticString {
+	s: &'static str,
+	interned: OnceLock<String>
+}
+
+impl StaticString {
+	pub const fn new(s: &'static str) -> Self {
+		Self { s, interned: OnceLock::new() }
+	}
+
+	/// Returns the string without interning it
+	pub const fn as_str(&self) -> &'static str {
+		self.s
+	}
+
+	/// Returns the interned string, interning it first if it hasn't been yet
+	pub fn get(&self) -> &String {
+		self.interned.get_or_init(|| String::from(self.s))
+	}
+}
+
+impl Deref for StaticString {
+	type Target = String;
+
+	fn deref(&self) -> &String {
+		self.get()
+	}
+}
+
+impl Debug for StaticString {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		Debug::fmt(self.s, f)
+	}
+}
+
+/// Perfect hashed table of [`StaticString`]s, as generated by [`Codegen`]
+pub struct StaticTable {
+	seed: u64,
+	displacements: &'static [(u32, u32)],
+	entries: &'static [&'static StaticString]
+}
+
+impl StaticTable {
+	/// Only meant to be called from generated code
+	#[doc(hidden)]
+	pub const fn new(seed: u64, displacements: &'static [(u32, u32)], entries: &'static [&'static StaticString]) -> Self {
+		Self { seed, displacements, entries }
+	}
+
+	/// Looks up a string in the table
+	pub fn get(&self, s: &str) -> Option<&'static StaticString> {
+		if self.entries.is_empty() { return None }
+
+		let hashes = Hashes::new(self.seed, s.as_bytes());
+		let (d1, d2) = self.displacements[hashes.g as usize % self.displacements.len()];
+		let entry = self.entries[hashes.index(d1, d2, self.entries.len())];
+
+		(entry.as_str() == s).then_some(entry)
+	}
+
+	/// Number of strings in the table
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Iterates through the strings in the table, in no particular order
+	pub fn iter(&self) -> impl Iterator<Item = &'static StaticString> {
+		self.entries.iter().copied()
+	}
+}
+
+impl Debug for StaticTable {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_set()
+			.entries(self.iter())
+			.finish()
+	}
+}
+
+/// Hashes used to place a word in the table. These are part of the generated
+/// code's contract, so they must never change.
+struct Hashes {
+	g: u32,
+	f1: u32,
+	f2: u32
+}
+
+impl Hashes {
+	fn new(seed: u64, bytes: &[u8]) -> Self {
+		let h1 = hash(seed, bytes);
+		let h2 = hash(seed ^ 0x9e3779b97f4a7c15, bytes);
+		Self { g: (h1 >> 32) as u32, f1: h1 as u32, f2: h2 as u32 }
+	}
+
+	fn index(&self, d1: u32, d2: u32, len: usize) -> usize {
+		let index = d2
+			.wrapping_add(self.f1.wrapping_mul(d1))
+			.wrapping_add(self.f2);
+		index as usize % len
+	}
+}
+
+/// Seeded FNV-1a, finished with the splitmix64 finaliser
+fn hash(seed: u64, bytes: &[u8]) -> u64 {
+	let mut hash = 0xcbf29ce484222325 ^ seed;
+	for &byte in bytes {
+		hash ^= byte as u64;
+		hash = hash.wrapping_mul(0x100000001b3);
+	}
+
+	hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+	hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+	hash ^ (hash >> 31)
+}
+
+/// Generates Rust source for a [`StaticTable`] and a [`StaticString`] static
+/// for every word. See the [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Codegen {
+	table_name: std::string::String,
+	prefix: std::string::String,
+	crate_path: std::string::String,
+	/// in the order they were added
+	words: Vec<std::string::String>,
+	/// same as `words`, to check for duplicates
+	word_set: HashSet<std::string::String>
+}
+
+impl Codegen {
+	/// `table_name` is the name of the generated [`StaticTable`] static
+	pub fn new(table_name: &str) -> Self {
+		Self {
+			table_name: table_name.into(),
+			prefix: std::string::String::new(),
+			crate_path: "::string_pool".into(),
+			words: Vec::new(),
+			word_set: HashSet::new()
+		}
+	}
+
+	/// Prefix for the names of the generated statics. The rest of the name is
+	/// the word in uppercase, with anything other than ASCII letters and digits
+	/// replaced with `_`. Defaults to no prefix.
+	pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+		self.prefix = prefix.into();
+		self
+	}
+
+	/// Path this crate is available at in the generated code, in case it was
+	/// renamed. Defaults to `::string_pool`.
+	pub fn crate_path(&mut self, crate_path: &str) -> &mut Self {
+		self.crate_path = crate_path.into();
+		self
+	}
+
+	/// Adds a word. Adding the same word more than once only adds it once.
+	pub fn word(&mut self, word: &str) -> &mut Self {
+		if !self.word_set.contains(word) {
+			self.word_set.insert(word.into());
+			self.words.push(word.into());
+		}
+		self
+	}
+
+	pub fn words<I, S>(&mut self, words: I) -> &mut Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>
+	{
+		words.into_iter().for_each(|word| { self.word(word.as_ref()); });
+		self
+	}
+
+	/// Generates the source and writes it to `writer`. Fails with
+	/// [`InvalidInput`](io::ErrorKind::InvalidInput) if two words would get the
+	/// same static name (or the same name as the table), or if there's no
+	/// prefix and a word has no ASCII letters or digits in it. Also fails if no
+	/// perfect hash is found for the words, which shouldn't happen in practice.
+	pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+		writer.write_all(self.generate()?.as_bytes())?;
+		writer.flush()
+	}
+
+	/// Generates the source, returning it as a string. See [`write`](Self::write).
+	pub fn generate(&self) -> io::Result<std::string::String> {
+		let names = self.words.iter()
+			.map(|word| self.static_name(word))
+			.collect::<io::Result<Vec<_>>>()?;
+		let mut seen = HashSet::with_capacity(names.len() + 1);
+		seen.insert(self.table_name.as_str());
+		for name in &names {
+			if !seen.insert(name.as_str()) {
+				let msg = format!("more than one static would be called `{name}`");
+				return Err(io::Error::new(io::ErrorKind::InvalidInput, msg))
+			}
+		}
+
+		let table = PerfectHash::generate(&self.words)?;
+		let krate = &self.crate_path;
+
+		let mut out = "// @generated by string_pool::codegen, do not edit\n\n".to_owned();
+		for (word, name) in self.words.iter().zip(&names) {
+			out += &format!("pub static {name}: {krate}::codegen::StaticString = {krate}::codegen::StaticString::new({word:?});\n");
+		}
+
+		let displacements = table.displacements.iter()
+			.map(|(d1, d2)| format!("({d1}, {d2})"))
+			.collect::<Vec<_>>()
+			.join(", ");
+		let entries = table.entries.iter()
+			.map(|&i| format!("&{}", names[i]))
+			.collect::<Vec<_>>()
+			.join(", ");
+
+		out += &format!(
+			"\npub static {}: {krate}::codegen::StaticTable = {krate}::codegen::StaticTable::new({}, &[{displacements}], &[{entries}]);\n",
+			self.table_name,
+			table.seed
+		);
+
+		Ok(out)
+	}
+
+	fn static_name(&self, word: &str) -> io::Result<std::string::String> {
+		// would only be underscores, which is either not a valid name (`_`), or
+		// a meaningless one
+		if self.prefix.is_empty() && !word.contains(|c: char| c.is_ascii_alphanumeric()) {
+			let msg = format!("word {word:?} needs a prefix to get a name");
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, msg))
+		}
+
+		let mut name = self.prefix.clone();
+		if name.is_empty() && !word.starts_with(|c: char| c.is_ascii_alphabetic()) { name.push('_') }
+
+		name.extend(word.chars().map(|c| match c {
+			c if c.is_ascii_alphanumeric() => { c.to_ascii_uppercase() }
+			_ => { '_' }
+		}));
+		Ok(name)
+	}
+}
+
+/// Perfect hash for a set of words, using the "hash, displace, and compress"
+/// (CHD) algorithm, like `phf` does
+struct PerfectHash {
+	seed: u64,
+	displacements: Vec<(u32, u32)>,
+	/// Index into the word list, for every slot in the table
+	entries: Vec<usize>
+}
+
+impl PerfectHash {
+	fn generate(words: &[std::string::String]) -> io::Result<Self> {
+		(0..MAX_SEEDS)
+			.find_map(|seed| Self::try_generate(seed, words))
+			.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no perfect hash found for the words"))
+	}
+
+	fn try_generate(seed: u64, words: &[std::string::String]) -> Option<Self> {
+		let len = words.len();
+		if len == 0 { return Some(Self { seed, displacements: Vec::new(), entries: Vec::new() }) }
+
+		let hashes = words.iter()
+			.map(|word| Hashes::new(seed, word.as_bytes()))
+			.collect::<Vec<_>>();
+
+		let bucket_count = len.div_ceil(LAMBDA);
+		let mut buckets = vec![Vec::new(); bucket_count];
+		hashes.iter().enumerate()
+			.for_each(|(i, hashes)| buckets[hashes.g as usize % bucket_count].push(i));
+
+		// place the biggest buckets first, while there's still lots of room
+		let mut order = (0..bucket_count).collect::<Vec<_>>();
+		order.sort_by_key(|&b| std::cmp::Reverse(buckets[b].len()));
+
+		let mut displacements = vec![(0, 0); bucket_count];
+		let mut slots = vec![None; len];
+		let mut placed = Vec::new();
+
+		let max_displacement = (len as u32).min(MAX_DISPLACEMENT);
+		'buckets: for b in order {
+			for d1 in 0..max_displacement {
+				'displacement: for d2 in 0..max_displacement {
+					placed.clear();
+
+					for &word in &buckets[b] {
+						let slot = hashes[word].index(d1, d2, len);
+						if slots[slot].is_some() || placed.contains(&slot) { continue 'displacement }
+						placed.push(slot);
+					}
+
+					buckets[b].iter().zip(&placed).for_each(|(&word, &slot)| slots[slot] = Some(word));
+					displacements[b] = (d1, d2);
+					continue 'buckets
+				}
+			}
+
+			// no displacement works for this bucket, try another seed
+			return None
+		}
+
+		let entries = slots.into_iter()
+			.map(|slot| slot.expect("every slot to be filled"))
+			.collect();
+		Some(Self { seed, displacements, entries })
+	}
+}
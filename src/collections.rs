@@ -0,0 +1,325 @@
+//! Collections keyed by strings in the [`GlobalPool`].
+//!
+//! Since equal strings in the global pool all share the same allocation, these
+//! don't need to hash or compare the contents of keys at all. Keys are hashed
+//! using the hash the pool already computed when interning them, and compared
+//! by pointer.
+
+use crate::pool::{ GlobalPool, Pool, SlicesWrap };
+use crate::string::String;
+use ::hashbrown::{ Equivalent, HashMap };
+use ::std::fmt::{ self, Debug };
+use ::std::hash::{ BuildHasherDefault, Hash, Hasher };
+use ::std::sync::Arc;
+
+type Raw = <GlobalPool as Pool>::Raw;
+
+#[cfg(test)]
+#[path = "./tests/collections.rs"]
+mod tests;
+
+/// Hasher that passes through a single `u64` written to it, for use with keys
+/// that hash themselves by writing a precomputed hash (see
+/// [`String::precomputed_hash`]).
+///
+/// Anything else written to it is folded into the state one word at a time,
+/// like FxHash does. That works, but it's a weak hash, so this is only a good
+/// choice for keys that write a single, already well distributed `u64`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrecomputedHasher(u64);
+
+/// [`BuildHasher`](::std::hash::BuildHasher) for [`PrecomputedHasher`]
+pub type BuildPrecomputedHasher = BuildHasherDefault<PrecomputedHasher>;
+
+impl PrecomputedHasher {
+	/// Folds `word` into the state. Starting from the default state of 0, the
+	/// first word is passed through unchanged.
+	fn fold(&mut self, word: u64) {
+		self.0 = self.0.rotate_left(5).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95) ^ word;
+	}
+}
+
+impl Hasher for PrecomputedHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		let mut chunks = bytes.chunks_exact(8);
+		for chunk in &mut chunks {
+			self.fold(u64::from_le_bytes(chunk.try_into().unwrap()));
+		}
+
+		let rest = chunks.remainder();
+		if !rest.is_empty() {
+			let mut word = [0u8; 8];
+			word[..rest.len()].copy_from_slice(rest);
+			// mixing in the length, so trailing zero bytes still make a difference
+			self.fold(u64::from_le_bytes(word) ^ ((rest.len() as u64) << 59));
+		}
+	}
+
+	fn write_u64(&mut self, i: u64) {
+		self.fold(i);
+	}
+}
+
+/// Something that can be used to look up a key in an [`InternedMap`] or
+/// [`InternedSet`]
+///
+/// Looking up by [`String`] is the fast path, it goes straight to the map.
+/// Looking up by `&str` is the slow path: the string is hashed in full and
+/// looked up in the pool first (taking its read lock, without the `lock-free`
+/// feature), before the map itself is probed. That is about as fast as a
+/// lookup in a `HashMap<std::string::String, V>`, not faster.
+pub trait InternedKey {
+	/// Finds the raw value in the global pool for this key, calling `f` with it
+	/// if it exists. If it doesn't exist in the pool, it can't be a key in any
+	/// map or set either.
+	fn with_raw<T>(&self, f: impl FnOnce(&Raw) -> Option<T>) -> Option<T>;
+}
+
+impl InternedKey for str {
+	fn with_raw<T>(&self, f: impl FnOnce(&Raw) -> Option<T>) -> Option<T> {
+		GlobalPool.with_raw(SlicesWrap(&[self.as_bytes()]), f)?
+	}
+}
+
+impl InternedKey for String {
+	fn with_raw<T>(&self, f: impl FnOnce(&Raw) -> Option<T>) -> Option<T> {
+		f(self.raw())
+	}
+}
+
+/// Map key, hashed by precomputed hash and compared by pointer
+struct Key(String);
+
+impl Hash for Key {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		KeyRef(self.0.raw()).hash(state)
+	}
+}
+
+impl PartialEq for Key {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(self.0.raw(), other.0.raw())
+	}
+}
+
+impl Eq for Key {}
+
+/// Borrowed [`Key`], to look up keys without having to clone the `Arc`
+struct KeyRef<'h>(&'h Raw);
+
+impl<'h> Hash for KeyRef<'h> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		state.write_u64(self.0.precomputed_hash())
+	}
+}
+
+impl<'h> Equivalent<Key> for KeyRef<'h> {
+	fn equivalent(&self, key: &Key) -> bool {
+		Arc::ptr_eq(self.0, key.0.raw())
+	}
+}
+
+/// Hash map keyed by strings in the [`GlobalPool`], hashing and comparing keys
+/// by their pooled allocation instead of their contents.
+///
+/// Looking up by [`String`] never touches the pool, and doesn't hash or
+/// compare the string's contents at all, which is where this beats a
+/// `HashMap<std::string::String, V>`. Looking up by `&str` first looks the
+/// string up in the pool, and if it isn't in there, it can't be in the map
+/// either. The map itself is then probed with the hash the pool stored, so the
+/// string is only hashed once, but that still costs about the same as a lookup
+/// in a `HashMap<std::string::String, V>`. Only lookups by `String` are faster
+/// than std; see [`InternedKey`].
+pub struct InternedMap<V> {
+	map: HashMap<Key, V, BuildPrecomputedHasher>
+}
+
+impl<V> InternedMap<V> {
+	pub fn new() -> Self {
+		Self { map: HashMap::default() }
+	}
+
+	pub fn with_capacity(capacity: usize) -> Self {
+		let map = HashMap::with_capacity_and_hasher(capacity, Default::default());
+		Self { map }
+	}
+
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.map.clear()
+	}
+
+	pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+		self.map.insert(Key(key), value)
+	}
+
+	pub fn get<Q: ?Sized + InternedKey>(&self, key: &Q) -> Option<&V> {
+		key.with_raw(|raw| self.map.get(&KeyRef(raw)))
+	}
+
+	pub fn get_key_value<Q: ?Sized + InternedKey>(&self, key: &Q) -> Option<(&String, &V)> {
+		key.with_raw(|raw| self.map.get_key_value(&KeyRef(raw)))
+			.map(|(k, v)| (&k.0, v))
+	}
+
+	pub fn get_mut<Q: ?Sized + InternedKey>(&mut self, key: &Q) -> Option<&mut V> {
+		key.with_raw(|raw| self.map.get_mut(&KeyRef(raw)))
+	}
+
+	pub fn contains_key<Q: ?Sized + InternedKey>(&self, key: &Q) -> bool {
+		self.get(key).is_some()
+	}
+
+	pub fn remove<Q: ?Sized + InternedKey>(&mut self, key: &Q) -> Option<V> {
+		self.remove_entry(key).map(|(_, v)| v)
+	}
+
+	pub fn remove_entry<Q: ?Sized + InternedKey>(&mut self, key: &Q) -> Option<(String, V)> {
+		key.with_raw(|raw| self.map.remove_entry(&KeyRef(raw)))
+			.map(|(k, v)| (k.0, v))
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &V)> {
+		self.map.iter().map(|(k, v)| (&k.0, v))
+	}
+
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut V)> {
+		self.map.iter_mut().map(|(k, v)| (&k.0, v))
+	}
+
+	pub fn keys(&self) -> impl Iterator<Item = &String> {
+		self.map.keys().map(|k| &k.0)
+	}
+
+	pub fn values(&self) -> impl Iterator<Item = &V> {
+		self.map.values()
+	}
+}
+
+impl<V> Default for InternedMap<V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<V: Clone> Clone for InternedMap<V> {
+	fn clone(&self) -> Self {
+		let map = self.map.iter()
+			.map(|(k, v)| (Key(k.0.clone()), v.clone()))
+			.collect();
+		Self { map }
+	}
+}
+
+impl<V: Debug> Debug for InternedMap<V> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_map()
+			.entries(self.iter().map(|(k, v)| (k.as_str(), v)))
+			.finish()
+	}
+}
+
+impl<V> Extend<(String, V)> for InternedMap<V> {
+	fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+		self.map.extend(iter.into_iter().map(|(k, v)| (Key(k), v)))
+	}
+}
+
+impl<V> FromIterator<(String, V)> for InternedMap<V> {
+	fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+		let mut map = Self::new();
+		map.extend(iter);
+		map
+	}
+}
+
+/// Hash set of strings in the [`GlobalPool`], hashing and comparing them by
+/// their pooled allocation instead of their contents.
+///
+/// Like [`InternedMap`], only lookups by [`String`] are faster than in a
+/// `HashSet<std::string::String>`; lookups by `&str` cost about the same.
+#[derive(Clone, Default)]
+pub struct InternedSet {
+	map: InternedMap<()>
+}
+
+impl InternedSet {
+	pub fn new() -> Self {
+		Self { map: InternedMap::new() }
+	}
+
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self { map: InternedMap::with_capacity(capacity) }
+	}
+
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.map.clear()
+	}
+
+	/// Returns `true` if the string was not already in the set
+	pub fn insert(&mut self, value: String) -> bool {
+		self.map.insert(value, ()).is_none()
+	}
+
+	pub fn contains<Q: ?Sized + InternedKey>(&self, value: &Q) -> bool {
+		self.map.contains_key(value)
+	}
+
+	pub fn get<Q: ?Sized + InternedKey>(&self, value: &Q) -> Option<&String> {
+		self.map.get_key_value(value).map(|(k, _)| k)
+	}
+
+	/// Returns `true` if the string was in the set
+	pub fn remove<Q: ?Sized + InternedKey>(&mut self, value: &Q) -> bool {
+		self.map.remove(value).is_some()
+	}
+
+	pub fn take<Q: ?Sized + InternedKey>(&mut self, value: &Q) -> Option<String> {
+		self.map.remove_entry(value).map(|(k, _)| k)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &String> {
+		self.map.keys()
+	}
+}
+
+impl Debug for InternedSet {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_set()
+			.entries(self.iter().map(|s| s.as_str()))
+			.finish()
+	}
+}
+
+impl Extend<String> for InternedSet {
+	fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+		self.map.extend(iter.into_iter().map(|s| (s, ())))
+	}
+}
+
+impl FromIterator<String> for InternedSet {
+	fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+		let mut set = Self::new();
+		set.extend(iter);
+		set
+	}
+}
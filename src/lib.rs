@@ -6,8 +6,11 @@
 
 #![allow(clippy::new_without_default)]
 
+pub mod codegen;
+pub mod collections;
 pub mod pool;
 pub mod string;
+pub mod table;
 
 #[doc(inline)]
 pub use crate::string::String;
@@ -1,9 +1,50 @@
+use ::std::borrow::Cow;
 use ::std::hash::{ Hash, Hasher };
 use ::hashbrown::HashSet;
 
+mod arena;
+mod bounded;
+mod canonical;
+pub mod conformance;
+mod fallback;
+mod frozen;
+mod generational;
 mod global;
+mod index;
+#[cfg(feature = "lock-free")]
+mod lock_free;
+mod lru;
+#[cfg(feature = "mmap")]
+mod mapped;
+#[cfg(feature = "unicode-normalization")]
+mod normalizing;
+mod selective;
+#[cfg(all(unix, feature = "shm"))]
+mod shm;
+pub(crate) mod snapshot;
+mod sso;
+#[cfg(feature = "thread-cache")]
+mod thread_cache;
+mod ttl;
 
+pub use arena::ArenaPool;
+pub use bounded::BoundedPool;
+pub use canonical::{ AsciiLowercase, Canonicalizer, CanonicalizingPool, CollapseWhitespace, Trim };
+pub use fallback::FallbackPool;
+pub use frozen::FrozenPool;
+pub use generational::GenerationalPool;
 pub use global::GlobalPool;
+pub use lru::LruPool;
+#[cfg(feature = "mmap")]
+pub use mapped::{ MappedPool, MappedPoolBuilder };
+#[cfg(feature = "unicode-normalization")]
+pub use normalizing::{ NormalizingPool, UnicodeNormalizer };
+pub use selective::{ FrequencyThreshold, InternPolicy, MaxLen, Predicate, SelectivePool };
+#[cfg(all(unix, feature = "shm"))]
+pub use shm::ShmPool;
+pub use snapshot::SnapshotError;
+pub use sso::SsoPool;
+pub use ttl::{ Clock, SystemClock, TtlPool };
 
 pub trait Pool: Clone {
 	type Raw;
@@ -20,6 +61,18 @@ pub trait Pool: Clone {
 
 	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8];
 
+	// --- optional functions ---
+
+	/// Looks up the provided slices in the pool, returning the existing raw
+	/// value if it's already in there, without inserting it if it isn't.
+	///
+	/// Pools that don't deduplicate strings (or can't look them up without
+	/// inserting them) should return `None`, which is what the default
+	/// implementation does.
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		None
+	}
+
 	// --- can-be-optimised functions ---
 	// these can be overridden if it can create a more efficient implementation
 
@@ -73,10 +126,62 @@ pub trait Pool: Clone {
 		let slice = self.raw_to_slice(raw);
 		unsafe { self.raw_from_slice(slice) }
 	}
+
+	/// Returns the hash of the string computed when it was interned, if the
+	/// pool stores one. The default implementation returns `None`.
+	///
+	/// Hashes are only meaningful compared to other hashes from the same pool.
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		None
+	}
+}
+
+/// Raw value for pools that wrap another pool, but may choose to not store
+/// some strings in it. Owned strings are never shared with anything else.
+#[derive(Debug)]
+pub enum PooledOrOwned<R> {
+	Pooled(R),
+	Owned(Box<[u8]>)
+}
+
+impl<R> PooledOrOwned<R> {
+	pub fn is_pooled(&self) -> bool {
+		matches!(self, Self::Pooled(_))
+	}
+
+	// helpers for implementing Pool for wrapping pools
+
+	pub(crate) fn as_slice<'r, P: Pool<Raw = R>>(&'r self, pool: &P) -> &'r [u8] {
+		match self {
+			Self::Pooled(raw) => { pool.raw_to_slice(raw) }
+			Self::Owned(bytes) => { bytes }
+		}
+	}
+
+	pub(crate) fn into_vec<P: Pool<Raw = R>>(self, pool: &P) -> Vec<u8> {
+		match self {
+			Self::Pooled(raw) => { pool.raw_into_vec(raw) }
+			Self::Owned(bytes) => { bytes.into_vec() }
+		}
+	}
+
+	pub(crate) fn clone_in<P: Pool<Raw = R>>(&self, pool: &P) -> Self {
+		match self {
+			Self::Pooled(raw) => { Self::Pooled(pool.raw_clone(raw)) }
+			Self::Owned(bytes) => { Self::Owned(bytes.clone()) }
+		}
+	}
+
+	pub(crate) fn precomputed_hash<P: Pool<Raw = R>>(&self, pool: &P) -> Option<u64> {
+		match self {
+			Self::Pooled(raw) => { pool.raw_precomputed_hash(raw) }
+			Self::Owned(_) => { None }
+		}
+	}
 }
 
 /// Wraps a slice of slices of bytes. This has a simple Hash and Eq implementation
-/// that just repeatedly hashes/checks every byte on every u8 in sequence, to ensure
+/// that hashes/checks the bytes in sequence as if the slices were joined, to ensure
 /// that no matter how many slices there are, as long as they have the same byte
 /// sequence, they will have the same resulting hash and compare equal. This way,
 /// we don't need to copy all the bytes into a new allocation until the absolute
@@ -86,6 +191,16 @@ pub trait Pool: Clone {
 pub struct SlicesWrap<'h>(pub &'h [&'h [u8]]);
 
 impl<'h> SlicesWrap<'h> {
+	/// Total length in bytes of all the slices, ie. the length the joined
+	/// slices would have.
+	pub fn len(&self) -> usize {
+		self.0.iter().map(|s| s.len()).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.iter().all(|s| s.is_empty())
+	}
+
 	pub fn to_vec(&self) -> Vec<u8> {
 		self.into_iter().collect()
 	}
@@ -94,14 +209,69 @@ impl<'h> SlicesWrap<'h> {
 	pub fn to_boxed_slice(&self) -> Box<[u8]> {
 		self.to_vec().into_boxed_slice()
 	}
+
+	/// Joins the slices, only allocating if there's more than one slice.
+	pub(crate) fn joined(&self) -> Cow<'h, [u8]> {
+		match self.0 {
+			[] => { Cow::Borrowed(&[]) }
+			[slice] => { Cow::Borrowed(slice) }
+			_ => { Cow::Owned(self.to_vec()) }
+		}
+	}
+
+	/// Checks if the joined slices are equal to `bytes`, without joining them.
+	pub(crate) fn eq_bytes(&self, mut bytes: &[u8]) -> bool {
+		if self.len() != bytes.len() { return false }
+
+		self.0.iter().all(|slice| {
+			let (head, rest) = bytes.split_at(slice.len());
+			bytes = rest;
+			head == *slice
+		})
+	}
 }
 
 impl<'h> Hash for SlicesWrap<'h> {
 	fn hash<H: Hasher>(&self, state: &mut H) {
-		self.into_iter().for_each(|b| state.write_u8(b));
+		hash_joined(self.0, state)
 	}
 }
 
+/// Feeds the joined bytes of `slices` to `state` 8 at a time as a `u64`, with
+/// whatever is left at the end fed one byte at a time, no matter where the
+/// slices are split. Hashing a word at a time instead of every byte separately
+/// is a lot faster for longer strings.
+pub(crate) fn hash_joined<H: Hasher>(slices: &[&[u8]], state: &mut H) {
+	let mut buf = [0u8; 8];
+	let mut buf_len = 0;
+
+	for slice in slices {
+		let mut slice = *slice;
+
+		// top up the bytes left over from the previous slice first
+		if buf_len > 0 {
+			let n = (buf.len() - buf_len).min(slice.len());
+			buf[buf_len..buf_len + n].copy_from_slice(&slice[..n]);
+			buf_len += n;
+			slice = &slice[n..];
+
+			if buf_len < buf.len() { continue }
+			state.write_u64(u64::from_le_bytes(buf));
+		}
+
+		let mut words = slice.chunks_exact(8);
+		for word in &mut words {
+			state.write_u64(u64::from_le_bytes(word.try_into().unwrap()));
+		}
+
+		let rest = words.remainder();
+		buf[..rest.len()].copy_from_slice(rest);
+		buf_len = rest.len();
+	}
+
+	buf[..buf_len].iter().for_each(|&b| state.write_u8(b));
+}
+
 impl<'h> IntoIterator for &SlicesWrap<'h> {
 	type Item = <SlicesWrapIter<'h> as Iterator>::Item;
 	type IntoIter = SlicesWrapIter<'h>;
@@ -0,0 +1,441 @@
+use super::{ Pool, SlicesWrap, SnapshotError };
+use super::snapshot;
+use ::hashbrown::HashMap;
+use ::hashbrown::hash_map::RawEntryMut;
+use ::parking_lot::RwLock;
+use ::std::cell::UnsafeCell;
+use ::std::fmt::{ self, Debug };
+use ::std::hash::BuildHasher;
+use ::std::io::{ self, Read, Write };
+use ::std::mem::size_of;
+use ::std::ptr;
+use ::std::sync::Arc;
+use ::std::process;
+use ::std::sync::atomic::{ AtomicUsize, Ordering };
+
+/// A pool that stores the bytes of its strings packed together into large
+/// chunks, instead of in one allocation per string.
+///
+/// Raw values are a handle to the chunk, and the offset and length of the
+/// string inside it. Every entry has a small header in front of it in the
+/// chunk, containing a refcount and the hash computed when it was interned.
+/// Chunks are freed when the last handle into it is dropped.
+///
+/// Unlike [`GlobalPool`](super::GlobalPool), every `ArenaPool` is its own
+/// separate pool. Clones of an `ArenaPool` share the same storage.
+#[derive(Clone)]
+pub struct ArenaPool {
+	inner: Arc<RwLock<ArenaInner>>
+}
+
+#[cfg(test)]
+#[path = "../tests/arena_pool.rs"]
+mod tests;
+
+struct ArenaInner {
+	index: HashMap<ArenaRaw, ()>,
+	chunks: Chunks
+}
+
+/// Allocates space for entries in chunks
+struct Chunks {
+	chunk_size: usize,
+	/// chunk that new entries get appended to
+	current: Arc<Chunk>,
+	/// bytes used in `current`
+	used: usize,
+	/// total bytes of chunks ever allocated
+	allocated: usize
+}
+
+impl ArenaPool {
+	/// Default size of a chunk, in bytes
+	pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+	pub fn new() -> Self {
+		Self::with_chunk_size(Self::DEFAULT_CHUNK_SIZE)
+	}
+
+	/// Creates a pool that allocates chunks of `chunk_size` bytes. Strings that
+	/// don't fit in a chunk get a chunk of their own.
+	pub fn with_chunk_size(chunk_size: usize) -> Self {
+		let current = Chunk::new(chunk_size);
+		let chunks = Chunks {
+			chunk_size,
+			allocated: current.capacity(),
+			current,
+			used: 0
+		};
+		let inner = ArenaInner { index: HashMap::new(), chunks };
+		Self { inner: Arc::new(RwLock::new(inner)) }
+	}
+
+	/// Number of strings in the pool
+	pub fn len(&self) -> usize {
+		self.inner.read().index.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Writes every string in the pool to `writer`. See
+	/// [`GlobalPool::save_snapshot`](super::GlobalPool::save_snapshot).
+	pub fn save_snapshot<W: Write>(&self, writer: W) -> io::Result<()> {
+		let inner = self.inner.read();
+		snapshot::write(writer, inner.index.keys().map(|raw| raw.as_bytes()))
+	}
+
+	/// Reads a snapshot, interning every string in it. See
+	/// [`GlobalPool::load_snapshot`](super::GlobalPool::load_snapshot).
+	///
+	/// Like any other string in the pool, loaded strings that aren't used by
+	/// anything are removed by the next [`collect_garbage`](Self::collect_garbage).
+	pub fn load_snapshot<R: Read>(&self, reader: R) -> Result<usize, SnapshotError> {
+		snapshot::read(reader, |s| {
+			let _ = unsafe { self.raw_from_slice(s.as_bytes()) };
+		})
+	}
+
+	/// Removes strings from the pool that aren't referenced by anything other
+	/// than the pool itself, returning how many were removed.
+	///
+	/// The space these strings took up in their chunks isn't reused; chunks are
+	/// freed once nothing references any string in them anymore. Use
+	/// [`compact`](Self::compact) to move strings that are still in the pool
+	/// out of sparsely used chunks.
+	pub fn collect_garbage(&self) -> usize {
+		let mut inner = self.inner.write();
+		let len = inner.index.len();
+
+		// can't race with interning, for the same reason as `Index::remove_unused`
+		inner.index.retain(|raw, _| raw.refs() > 1);
+		len - inner.index.len()
+	}
+
+	/// Copies strings out of chunks that have a lot of wasted space in them
+	/// (for example after [`collect_garbage`](Self::collect_garbage)) into
+	/// densely packed new chunks, freeing the old ones. Returns the number of
+	/// bytes reclaimed, ie. the size of the chunks freed minus the size of the
+	/// new chunks allocated.
+	///
+	/// Only space taken up by strings that were removed from the pool counts
+	/// as wasted, and only chunks where that is more than a quarter of the chunk
+	/// are compacted. Space left unused at the end of a chunk, because the next
+	/// string didn't fit in it anymore, doesn't count, since moving strings
+	/// around wouldn't get rid of that.
+	///
+	/// Strings that are still referenced outside of the pool can't be moved
+	/// without invalidating their handles, so chunks containing any of those
+	/// are left alone.
+	pub fn compact(&self) -> usize {
+		let mut inner = self.inner.write();
+		let ArenaInner { index, chunks } = &mut *inner;
+
+		let mut stats = HashMap::<*const Chunk, ChunkStats>::new();
+		for raw in index.keys() {
+			let stats = stats.entry(Arc::as_ptr(&raw.chunk))
+				.or_insert_with(|| ChunkStats {
+					entries: 0,
+					used: 0,
+					pinned: false,
+					chunk: Arc::clone(&raw.chunk)
+				});
+
+			stats.entries += 1;
+			stats.used += Chunk::entry_size(raw.len);
+			stats.pinned |= raw.refs() > 1;
+		}
+
+		let current = Arc::as_ptr(&chunks.current);
+		stats.retain(|ptr, stats| {
+			// strong count is index entries, plus the one in stats. If it's more
+			// than that, there are handles to strings no longer in the index
+			let unreferenced = Arc::strong_count(&stats.chunk) == stats.entries + 1;
+			// `filled` is only set once a chunk stops being the current one
+			*ptr != current
+				&& unreferenced
+				&& !stats.pinned
+				&& (stats.chunk.filled() - stats.used) * 4 > stats.chunk.capacity()
+		});
+
+		if stats.is_empty() { return 0 }
+
+		let released = stats.values()
+			.map(|stats| stats.chunk.capacity())
+			.sum::<usize>();
+		let allocated_before = chunks.allocated;
+
+		let to_move = index.keys()
+			.filter(|raw| stats.contains_key(&Arc::as_ptr(&raw.chunk)))
+			.map(|raw| (raw.hash(), Arc::as_ptr(&raw.chunk), raw.offset))
+			.collect::<Vec<_>>();
+		drop(stats);
+
+		for (hash, chunk, offset) in to_move {
+			let entry = index.raw_entry_mut()
+				.from_hash(hash, |raw| Arc::as_ptr(&raw.chunk) == chunk && raw.offset == offset);
+
+			if let RawEntryMut::Occupied(mut entry) = entry {
+				let bytes = entry.key().as_bytes();
+				let raw = chunks.alloc(hash, &SlicesWrap(&[bytes]));
+				entry.insert_key(raw);
+			}
+		}
+
+		released.saturating_sub(chunks.allocated - allocated_before)
+	}
+}
+
+/// Used in [`ArenaPool::compact`] to figure out which chunks can be compacted
+struct ChunkStats {
+	entries: usize,
+	used: usize,
+	pinned: bool,
+	chunk: Arc<Chunk>
+}
+
+impl Default for ArenaPool {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Debug for ArenaPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let inner = self.inner.read();
+		f.debug_struct("ArenaPool")
+			.field("len", &inner.index.len())
+			.field("chunk_size", &inner.chunks.chunk_size)
+			.finish()
+	}
+}
+
+impl Chunks {
+	/// Copies `slices` into the arena, returning a raw with a refcount of 1
+	fn alloc(&mut self, hash: u64, slices: &SlicesWrap) -> ArenaRaw {
+		let len = slices.len();
+		let size = Chunk::entry_size(len);
+
+		let (chunk, offset) = if size > self.chunk_size {
+			// too big for a chunk, gets a chunk all to itself
+			let chunk = Chunk::new(size);
+			chunk.set_filled(size);
+			self.allocated += chunk.capacity();
+			(chunk, 0)
+		} else {
+			if self.used + size > self.current.capacity() {
+				self.current.set_filled(self.used);
+				self.current = Chunk::new(self.chunk_size);
+				self.used = 0;
+				self.allocated += self.current.capacity();
+			}
+
+			let offset = self.used;
+			self.used += size;
+			(Arc::clone(&self.current), offset)
+		};
+
+		// SAFETY: offset..offset + size is in bounds, and has never been handed
+		// out before. We hold the write lock, so nobody else is writing to it
+		unsafe { chunk.write(offset, hash, slices) };
+		ArenaRaw { chunk, offset, len }
+	}
+}
+
+impl Pool for ArenaPool {
+	type Raw = ArenaRaw;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let inner = self.inner.read();
+		let hash = inner.index.hasher().hash_one(&slices);
+
+		if let Some((raw, _)) = inner.index.raw_entry().from_hash(hash, |raw| raw.matches(hash, &slices)) {
+			let raw = raw.clone();
+			drop(inner);
+			raw
+		} else {
+			drop(inner);
+
+			let mut inner = self.inner.write();
+			let ArenaInner { index, chunks } = &mut *inner;
+			match index.raw_entry_mut().from_hash(hash, |raw| raw.matches(hash, &slices)) {
+				RawEntryMut::Occupied(entry) => { entry.key().clone() }
+				RawEntryMut::Vacant(entry) => {
+					let raw = chunks.alloc(hash, &slices);
+					let (raw, _) = entry.insert_with_hasher(hash, raw, (), |raw| raw.hash());
+					raw.clone()
+				}
+			}
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw.as_bytes()
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		let inner = self.inner.read();
+		let hash = inner.index.hasher().hash_one(&slices);
+
+		inner.index.raw_entry()
+			.from_hash(hash, |raw| raw.matches(hash, &slices))
+			.map(|(raw, _)| raw.clone())
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		raw.clone()
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		Some(raw.hash())
+	}
+}
+
+/// Raw value of an [`ArenaPool`], a handle to a string stored in a chunk
+pub struct ArenaRaw {
+	chunk: Arc<Chunk>,
+	offset: usize,
+	len: usize
+}
+
+impl ArenaRaw {
+	pub fn as_bytes(&self) -> &[u8] {
+		// SAFETY: the entry was written before the raw was created, and
+		// entries are never written to again
+		unsafe { self.chunk.bytes(self.offset, self.len) }
+	}
+
+	fn header(&self) -> &EntryHeader {
+		// SAFETY: there's always a header at offset
+		unsafe { self.chunk.header(self.offset) }
+	}
+
+	fn hash(&self) -> u64 {
+		self.header().hash
+	}
+
+	/// Number of handles to this entry, including the one in the pool's index
+	/// (if it's still in the index)
+	fn refs(&self) -> usize {
+		self.header().refs.load(Ordering::Acquire)
+	}
+
+	fn matches(&self, hash: u64, slices: &SlicesWrap) -> bool {
+		self.hash() == hash && slices.eq_bytes(self.as_bytes())
+	}
+}
+
+impl Clone for ArenaRaw {
+	fn clone(&self) -> Self {
+		// like Arc, aborting well before the count could ever wrap around, since
+		// a wrapped count would let the entry be compacted away while in use
+		let refs = self.header().refs.fetch_add(1, Ordering::Relaxed);
+		if refs > isize::MAX as usize { process::abort() }
+
+		let chunk = Arc::clone(&self.chunk);
+		Self { chunk, offset: self.offset, len: self.len }
+	}
+}
+
+impl Drop for ArenaRaw {
+	fn drop(&mut self) {
+		self.header().refs.fetch_sub(1, Ordering::Release);
+	}
+}
+
+impl Debug for ArenaRaw {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ArenaRaw")
+			.field("chunk", &Arc::as_ptr(&self.chunk))
+			.field("offset", &self.offset)
+			.field("len", &self.len)
+			.finish()
+	}
+}
+
+/// Header in front of every entry in a chunk
+#[repr(C)]
+struct EntryHeader {
+	refs: AtomicUsize,
+	hash: u64
+}
+
+/// A chunk of arena storage. Memory is stored as `u64`s, so that every entry
+/// (which are padded to a multiple of 8 bytes) starts aligned for its header.
+struct Chunk {
+	words: Box<[UnsafeCell<u64>]>,
+	/// Bytes handed out to entries, set once the chunk is no longer the one
+	/// new entries get appended to
+	filled: AtomicUsize
+}
+
+// SAFETY: entries are only written to once, before any raw to them is created,
+// while holding the pool's write lock; after that, only the refcount in the
+// header is modified, which is atomic
+unsafe impl Send for Chunk {}
+unsafe impl Sync for Chunk {}
+
+impl Chunk {
+	fn new(capacity: usize) -> Arc<Self> {
+		let words = (0..capacity.div_ceil(8))
+			.map(|_| UnsafeCell::new(0))
+			.collect();
+		Arc::new(Self { words, filled: AtomicUsize::new(0) })
+	}
+
+	/// Size an entry of `len` bytes takes up in a chunk, including its header
+	/// and padding
+	fn entry_size(len: usize) -> usize {
+		size_of::<EntryHeader>() + len.next_multiple_of(8)
+	}
+
+	fn capacity(&self) -> usize {
+		self.words.len() * 8
+	}
+
+	fn filled(&self) -> usize {
+		self.filled.load(Ordering::Relaxed)
+	}
+
+	/// Only called with the pool's write lock held
+	fn set_filled(&self, filled: usize) {
+		self.filled.store(filled, Ordering::Relaxed)
+	}
+
+	fn ptr(&self) -> *mut u8 {
+		UnsafeCell::raw_get(self.words.as_ptr()).cast()
+	}
+
+	/// # Safety
+	///
+	/// `offset` must be the start of an entry.
+	unsafe fn header(&self, offset: usize) -> &EntryHeader {
+		&*self.ptr().add(offset).cast::<EntryHeader>()
+	}
+
+	/// # Safety
+	///
+	/// `offset` must be the start of an entry, and `len` its length.
+	unsafe fn bytes(&self, offset: usize, len: usize) -> &[u8] {
+		let ptr = self.ptr().add(offset + size_of::<EntryHeader>());
+		::std::slice::from_raw_parts(ptr, len)
+	}
+
+	/// Writes an entry with refcount 1.
+	///
+	/// # Safety
+	///
+	/// `offset` must be 8-aligned, there must be enough space for the entry,
+	/// and nothing else can be accessing that space.
+	unsafe fn write(&self, offset: usize, hash: u64, slices: &SlicesWrap) {
+		let header = EntryHeader { refs: AtomicUsize::new(1), hash };
+		self.ptr().add(offset).cast::<EntryHeader>().write(header);
+
+		let mut ptr = self.ptr().add(offset + size_of::<EntryHeader>());
+		for slice in slices.0 {
+			ptr::copy_nonoverlapping(slice.as_ptr(), ptr, slice.len());
+			ptr = ptr.add(slice.len());
+		}
+	}
+}
@@ -0,0 +1,136 @@
+use super::{ Pool, PooledOrOwned, SlicesWrap };
+use ::parking_lot::Mutex;
+use ::std::sync::Arc;
+use ::std::sync::atomic::{ AtomicUsize, Ordering };
+
+/// Wraps another pool, limiting how many strings (and how many bytes) can be
+/// inserted into it through this wrapper. Once either limit is reached, new
+/// strings are not inserted into the wrapped pool anymore, but instead get
+/// their own owned allocation that isn't shared with anything. These still work
+/// as a [`String`](crate::String) like any other, just without being deduplicated.
+///
+/// Strings already in the wrapped pool are always reused, regardless of the
+/// limits. This relies on the wrapped pool supporting [`Pool::raw_get`]; if
+/// it doesn't, every string counts towards the limits, even ones that are
+/// already in the pool.
+///
+/// Only strings inserted through this wrapper are counted. The counts are also
+/// approximate: if the same new string is inserted from multiple threads at the
+/// same time, it may be counted more than once.
+#[derive(Clone, Debug)]
+pub struct BoundedPool<P> {
+	pool: P,
+	state: Arc<BoundedState>
+}
+
+#[cfg(test)]
+#[path = "../tests/bounded_pool.rs"]
+mod tests;
+
+#[derive(Debug)]
+struct BoundedState {
+	max_entries: usize,
+	max_bytes: usize,
+	usage: Mutex<Usage>,
+	fallbacks: AtomicUsize
+}
+
+#[derive(Debug)]
+struct Usage {
+	entries: usize,
+	bytes: usize
+}
+
+impl<P: Pool> BoundedPool<P> {
+	pub fn new(pool: P, max_entries: usize, max_bytes: usize) -> Self {
+		let state = BoundedState {
+			max_entries,
+			max_bytes,
+			usage: Mutex::new(Usage { entries: 0, bytes: 0 }),
+			fallbacks: AtomicUsize::new(0)
+		};
+		Self { pool, state: Arc::new(state) }
+	}
+
+	/// Returns a reference to the wrapped pool
+	pub fn inner(&self) -> &P {
+		&self.pool
+	}
+
+	pub fn max_entries(&self) -> usize {
+		self.state.max_entries
+	}
+
+	pub fn max_bytes(&self) -> usize {
+		self.state.max_bytes
+	}
+
+	/// Number of strings inserted into the wrapped pool through this wrapper
+	pub fn entries(&self) -> usize {
+		self.state.usage.lock().entries
+	}
+
+	/// Number of bytes inserted into the wrapped pool through this wrapper
+	pub fn bytes(&self) -> usize {
+		self.state.usage.lock().bytes
+	}
+
+	/// Number of times a string didn't fit in the budget, and was stored in
+	/// its own allocation instead of being inserted into the wrapped pool
+	pub fn fallbacks(&self) -> usize {
+		self.state.fallbacks.load(Ordering::Relaxed)
+	}
+
+	/// Reserves space for a new string of `len` bytes, returning `false` if
+	/// it doesn't fit
+	fn reserve(&self, len: usize) -> bool {
+		let mut usage = self.state.usage.lock();
+
+		let fits = usage.entries < self.state.max_entries
+			&& usage.bytes + len <= self.state.max_bytes;
+
+		if fits {
+			usage.entries += 1;
+			usage.bytes += len;
+		}
+
+		fits
+	}
+}
+
+impl<P: Pool> Pool for BoundedPool<P> {
+	type Raw = PooledOrOwned<P::Raw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		if let Some(raw) = self.pool.raw_get(SlicesWrap(slices.0)) {
+			return PooledOrOwned::Pooled(raw)
+		}
+
+		if self.reserve(slices.len()) {
+			PooledOrOwned::Pooled(self.pool.raw_from_slices(slices))
+		} else {
+			self.state.fallbacks.fetch_add(1, Ordering::Relaxed);
+			PooledOrOwned::Owned(slices.to_boxed_slice())
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw.as_slice(&self.pool)
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		self.pool.raw_get(slices).map(PooledOrOwned::Pooled)
+	}
+
+	fn raw_into_vec(&self, raw: Self::Raw) -> Vec<u8> {
+		raw.into_vec(&self.pool)
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		raw.clone_in(&self.pool)
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		raw.precomputed_hash(&self.pool)
+	}
+}
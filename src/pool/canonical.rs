@@ -0,0 +1,200 @@
+use super::{ Pool, SlicesWrap };
+use crate::string::String;
+use ::std::borrow::Cow;
+use ::std::str as std_str;
+
+/// Wraps another pool, transforming strings into a canonical form using a
+/// [`Canonicalizer`] before interning them. For example, with
+/// [`AsciiLowercase`], `"Foo"` and `"FOO"` both become the same `"foo"` entry.
+///
+/// The contents of a [`String`] in this pool are the canonical form. The pool
+/// can optionally also keep the original spelling around (see
+/// [`with_original`](CanonicalizingPool::with_original)), which can then be
+/// retrieved with [`String::original`]. The original doesn't affect equality
+/// or hashing, which only look at the canonical form.
+#[derive(Clone, Debug)]
+pub struct CanonicalizingPool<P, C> {
+	pool: P,
+	canonicalizer: C,
+	keep_original: bool
+}
+
+#[cfg(test)]
+#[path = "../tests/canonicalizing_pool.rs"]
+mod tests;
+
+impl<P: Pool, C: Canonicalizer> CanonicalizingPool<P, C> {
+	pub fn new(pool: P, canonicalizer: C) -> Self {
+		Self { pool, canonicalizer, keep_original: false }
+	}
+
+	/// Creates a pool that keeps the original spelling of strings alongside
+	/// the canonical form, if they are different.
+	pub fn with_original(pool: P, canonicalizer: C) -> Self {
+		Self { pool, canonicalizer, keep_original: true }
+	}
+
+	/// Returns a reference to the wrapped pool
+	pub fn inner(&self) -> &P {
+		&self.pool
+	}
+
+	pub fn canonicalizer(&self) -> &C {
+		&self.canonicalizer
+	}
+
+	/// Canonicalizes the slices, passing the canonical form to `f`. Also
+	/// returns the original, if it should be kept.
+	///
+	/// # Safety
+	///
+	/// The joined slices must be valid UTF-8.
+	unsafe fn canonicalize<T>(
+		&self,
+		slices: &SlicesWrap,
+		f: impl FnOnce(SlicesWrap) -> T
+	) -> (T, Option<Box<str>>) {
+		let joined = slices.joined();
+		let s = std_str::from_utf8_unchecked(&joined);
+
+		match self.canonicalizer.canonicalize(s) {
+			// unchanged, so can pass on the original slices
+			Cow::Borrowed(canonical) if canonical == s => {
+				(f(SlicesWrap(slices.0)), None)
+			}
+			canonical => {
+				let original = self.keep_original.then(|| s.into());
+				(f(SlicesWrap(&[canonical.as_bytes()])), original)
+			}
+		}
+	}
+}
+
+/// Transforms strings into a canonical form for a [`CanonicalizingPool`].
+///
+/// Canonicalizers can be combined using tuples: `(A, B)` applies `A` first,
+/// and then `B`.
+pub trait Canonicalizer: Clone {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str>;
+}
+
+/// Lowercases ASCII letters, leaving other characters alone
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsciiLowercase;
+
+impl Canonicalizer for AsciiLowercase {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+		if s.bytes().any(|b| b.is_ascii_uppercase()) {
+			Cow::Owned(s.to_ascii_lowercase())
+		} else {
+			Cow::Borrowed(s)
+		}
+	}
+}
+
+/// Removes leading and trailing whitespace
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Trim;
+
+impl Canonicalizer for Trim {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+		Cow::Borrowed(s.trim())
+	}
+}
+
+/// Replaces every run of whitespace with a single space
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CollapseWhitespace;
+
+impl Canonicalizer for CollapseWhitespace {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+		let mut prev_whitespace = false;
+		let needs_collapsing = s.chars().any(|c| {
+			let needs = c.is_whitespace() && (prev_whitespace || c != ' ');
+			prev_whitespace = c.is_whitespace();
+			needs
+		});
+		if !needs_collapsing { return Cow::Borrowed(s) }
+
+		let mut collapsed = ::std::string::String::with_capacity(s.len());
+		let mut prev_whitespace = false;
+		for c in s.chars() {
+			if !c.is_whitespace() {
+				collapsed.push(c);
+			} else if !prev_whitespace {
+				collapsed.push(' ');
+			}
+			prev_whitespace = c.is_whitespace();
+		}
+
+		Cow::Owned(collapsed)
+	}
+}
+
+impl<A: Canonicalizer, B: Canonicalizer> Canonicalizer for (A, B) {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+		match self.0.canonicalize(s) {
+			Cow::Borrowed(s) => { self.1.canonicalize(s) }
+			Cow::Owned(s) => { Cow::Owned(self.1.canonicalize(&s).into_owned()) }
+		}
+	}
+}
+
+/// Raw value of a [`CanonicalizingPool`]
+#[derive(Debug)]
+pub struct CanonicalRaw<R> {
+	canonical: R,
+	/// only stored if the pool keeps originals, and it's different
+	/// from the canonical form
+	original: Option<Box<str>>
+}
+
+impl<R> CanonicalRaw<R> {
+	/// The original spelling, if it was kept and is different from the
+	/// canonical form
+	pub fn original(&self) -> Option<&str> {
+		self.original.as_deref()
+	}
+}
+
+impl<P: Pool, C: Canonicalizer> Pool for CanonicalizingPool<P, C> {
+	type Raw = CanonicalRaw<P::Raw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let (canonical, original) = self.canonicalize(&slices, |slices| {
+			self.pool.raw_from_slices(slices)
+		});
+		CanonicalRaw { canonical, original }
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		self.pool.raw_to_slice(&raw.canonical)
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		std_str::from_utf8(&slices.joined()).ok()?;
+
+		let (canonical, original) = unsafe {
+			self.canonicalize(&slices, |slices| self.pool.raw_get(slices))
+		};
+		Some(CanonicalRaw { canonical: canonical?, original })
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		let canonical = self.pool.raw_clone(&raw.canonical);
+		let original = raw.original.clone();
+		CanonicalRaw { canonical, original }
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		self.pool.raw_precomputed_hash(&raw.canonical)
+	}
+}
+
+impl<P: Pool, C: Canonicalizer> String<CanonicalizingPool<P, C>> {
+	/// The original spelling of this string, if the pool keeps those, or the
+	/// canonical form otherwise.
+	pub fn original(&self) -> &str {
+		self.raw().original().unwrap_or(self.as_str())
+	}
+}
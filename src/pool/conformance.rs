@@ -0,0 +1,369 @@
+//! Checks that a [`Pool`] implementation upholds the contract the rest of the
+//! crate relies on, but can't check at compile time:
+//!
+//! - raws round-trip the bytes they were created from (for pools that don't
+//!   transform strings, see [`idempotent`] for ones that do)
+//! - interning a [`SlicesWrap`] gives the same string as interning the joined
+//!   bytes, no matter where the slices are split (even in the middle of a char)
+//! - [`raw_clone`](Pool::raw_clone), [`raw_into_vec`](Pool::raw_into_vec) and
+//!   the other provided methods preserve the contents
+//! - [`raw_get`](Pool::raw_get) and [`raw_precomputed_hash`](Pool::raw_precomputed_hash)
+//!   agree with the strings the pool interns
+//!
+//! Every check panics if the pool doesn't hold up its end, so they can be
+//! called from tests directly. [`check`] runs all of them. The
+//! [`pool_conformance_tests!`](crate::pool_conformance_tests) macro generates
+//! a separate `#[test]` for each one:
+//!
+//! ```
+//! # use string_pool::pool::ArenaPool;
+//! # use string_pool::pool::conformance;
+//! conformance::check(&ArenaPool::new());
+//! ```
+//!
+//! ```ignore
+//! #[cfg(test)]
+//! mod tests {
+//!     use super::MyPool;
+//!
+//!     string_pool::pool_conformance_tests!(conformance, MyPool::new());
+//! }
+//! ```
+
+use super::{ Pool, SlicesWrap };
+use crate::string::SAMPLES;
+use ::std::str;
+use ::std::string::String as StdString;
+
+/// Generates a module named `$name`, with a `#[test]` for every check in
+/// [`pool::conformance`](crate::pool::conformance), each running it against
+/// the pool `$pool` evaluates to. `$pool` is evaluated once per test, inside
+/// the generated module (which glob imports its parent module).
+///
+/// Pools that transform strings before interning them (like
+/// [`CanonicalizingPool`](crate::pool::CanonicalizingPool)) don't round-trip
+/// their contents. Adding `canonicalizing` checks that they are
+/// [`idempotent`](crate::pool::conformance::idempotent) instead:
+///
+/// ```ignore
+/// string_pool::pool_conformance_tests!(conformance, CanonicalizingPool::new(GlobalPool, Trim), canonicalizing);
+/// ```
+#[macro_export]
+macro_rules! pool_conformance_tests {
+	(@tests $name:ident, $pool:expr, $contents:ident) => {
+		mod $name {
+			use super::*;
+
+			#[test]
+			fn $contents() {
+				$crate::pool::conformance::$contents(&$pool);
+			}
+
+			#[test]
+			fn empty() {
+				$crate::pool::conformance::empty(&$pool);
+			}
+
+			#[test]
+			fn slices_intern_like_joined() {
+				$crate::pool::conformance::slices_intern_like_joined(&$pool);
+			}
+
+			#[test]
+			fn raw_clone() {
+				$crate::pool::conformance::raw_clone(&$pool);
+			}
+
+			#[test]
+			fn conversions() {
+				$crate::pool::conformance::conversions(&$pool);
+			}
+
+			#[test]
+			fn raw_get() {
+				$crate::pool::conformance::raw_get(&$pool);
+			}
+
+			#[test]
+			fn precomputed_hash() {
+				$crate::pool::conformance::precomputed_hash(&$pool);
+			}
+
+			#[test]
+			fn pool_clones() {
+				$crate::pool::conformance::pool_clones(&$pool);
+			}
+
+			#[test]
+			fn many_strings() {
+				$crate::pool::conformance::many_strings(&$pool);
+			}
+		}
+	};
+
+	($name:ident, $pool:expr, canonicalizing) => {
+		$crate::pool_conformance_tests!(@tests $name, $pool, idempotent);
+	};
+
+	($name:ident, $pool:expr) => {
+		$crate::pool_conformance_tests!(@tests $name, $pool, round_trip);
+	};
+}
+
+/// Runs every check, for pools that don't transform strings
+pub fn check<P: Pool>(pool: &P) {
+	round_trip(pool);
+	check_common(pool);
+}
+
+/// Runs every check, for pools that transform strings before interning them
+pub fn check_canonicalizing<P: Pool>(pool: &P) {
+	idempotent(pool);
+	check_common(pool);
+}
+
+fn check_common<P: Pool>(pool: &P) {
+	empty(pool);
+	slices_intern_like_joined(pool);
+	raw_clone(pool);
+	conversions(pool);
+	raw_get(pool);
+	precomputed_hash(pool);
+	pool_clones(pool);
+	many_strings(pool);
+}
+
+/// Strings are interned with exactly the contents they were created from
+pub fn round_trip<P: Pool>(pool: &P) {
+	for s in samples() {
+		let raw = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		assert_contents(pool, &raw, &s, "raw_from_slice");
+
+		let raw = unsafe { pool.raw_from_vec(s.clone().into_bytes()) };
+		assert_contents(pool, &raw, &s, "raw_from_vec");
+
+		let raw = unsafe { pool.raw_from_slices(SlicesWrap(&[s.as_bytes()])) };
+		assert_contents(pool, &raw, &s, "raw_from_slices");
+	}
+}
+
+/// For pools that transform strings: interning a string's (transformed)
+/// contents again doesn't change them any further, and interning the same
+/// string again gives the same contents
+pub fn idempotent<P: Pool>(pool: &P) {
+	for s in samples() {
+		let raw = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		let contents = contents(pool, &raw);
+
+		let again = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		assert_contents(pool, &again, contents, "interning the same string twice");
+
+		let twice = unsafe { pool.raw_from_slice(contents.as_bytes()) };
+		assert_contents(pool, &twice, contents, "interning the interned contents");
+
+		let raw = unsafe { pool.raw_from_vec(s.clone().into_bytes()) };
+		assert_contents(pool, &raw, contents, "raw_from_vec");
+	}
+}
+
+/// The empty string can be created in all the ways it can be spelt
+pub fn empty<P: Pool>(pool: &P) {
+	assert_contents(pool, &pool.raw_empty(), "", "raw_empty");
+
+	let raw = unsafe { pool.raw_from_slice(&[]) };
+	assert_contents(pool, &raw, "", "raw_from_slice");
+
+	let raw = unsafe { pool.raw_from_vec(Vec::new()) };
+	assert_contents(pool, &raw, "", "raw_from_vec");
+
+	let raw = unsafe { pool.raw_from_slices(SlicesWrap(&[])) };
+	assert_contents(pool, &raw, "", "raw_from_slices with no slices");
+
+	let raw = unsafe { pool.raw_from_slices(SlicesWrap(&[&[], &[]])) };
+	assert_contents(pool, &raw, "", "raw_from_slices with empty slices");
+}
+
+/// Interning a [`SlicesWrap`] gives the same contents as interning the joined
+/// bytes, wherever the slices are split, including in the middle of a char and
+/// with empty slices in between
+pub fn slices_intern_like_joined<P: Pool>(pool: &P) {
+	for s in samples() {
+		let joined = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		let expected = contents(pool, &joined);
+		let hash = pool.raw_precomputed_hash(&joined);
+
+		let bytes = s.as_bytes();
+		// every split for short strings, a sample of them for long ones
+		let step = (bytes.len() / 32).max(1);
+
+		for i in (0..=bytes.len()).step_by(step) {
+			for j in (i..=bytes.len()).step_by(step) {
+				let (a, rest) = bytes.split_at(i);
+				let (b, c) = rest.split_at(j - i);
+
+				for slices in [&[a, b, c][..], &[a, &[], b, &[], c], &[&[], a, b, c, &[]]] {
+					let raw = unsafe { pool.raw_from_slices(SlicesWrap(slices)) };
+					assert_contents(pool, &raw, expected, &format!("raw_from_slices split at {i} and {j}"));
+
+					if let (Some(hash), Some(split_hash)) = (hash, pool.raw_precomputed_hash(&raw)) {
+						assert_eq!(hash, split_hash, "precomputed hash of {s:?} split at {i} and {j} differs from the joined one");
+					}
+				}
+			}
+		}
+	}
+}
+
+/// Cloned raws have the same contents as the original, and stay valid after
+/// the original is dropped
+pub fn raw_clone<P: Pool>(pool: &P) {
+	for s in samples() {
+		let raw = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		let expected = contents(pool, &raw).to_owned();
+
+		let clone = pool.raw_clone(&raw);
+		assert_contents(pool, &clone, &expected, "raw_clone");
+
+		drop(raw);
+		assert_contents(pool, &clone, &expected, "raw_clone after dropping the original");
+
+		let clone_of_clone = pool.raw_clone(&clone);
+		drop(clone);
+		assert_contents(pool, &clone_of_clone, &expected, "raw_clone of a clone");
+	}
+}
+
+/// Converting a raw into owned bytes preserves its contents
+pub fn conversions<P: Pool>(pool: &P) {
+	for s in samples() {
+		let raw = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		let expected = contents(pool, &raw).as_bytes().to_vec();
+
+		let vec = pool.raw_into_vec(pool.raw_clone(&raw));
+		assert_eq!(vec, expected, "raw_into_vec changed the contents of {s:?}");
+
+		let boxed = pool.raw_into_boxed_slice(raw);
+		assert_eq!(*boxed, *expected, "raw_into_boxed_slice changed the contents of {s:?}");
+	}
+}
+
+/// Anything [`raw_get`](Pool::raw_get) finds has the same contents as the
+/// string it was looking for would have when interned. Not finding it is
+/// always allowed.
+pub fn raw_get<P: Pool>(pool: &P) {
+	for s in samples() {
+		let bytes = s.as_bytes();
+		let (a, b) = bytes.split_at(bytes.len() / 2);
+
+		// before interning it, it might already be in there or not
+		if let Some(found) = pool.raw_get(SlicesWrap(&[bytes])) {
+			let raw = unsafe { pool.raw_from_slice(bytes) };
+			assert_contents(pool, &found, contents(pool, &raw), "raw_get before interning");
+		}
+
+		let raw = unsafe { pool.raw_from_slice(bytes) };
+		let expected = contents(pool, &raw);
+
+		for slices in [&[bytes][..], &[a, b]] {
+			if let Some(found) = pool.raw_get(SlicesWrap(slices)) {
+				assert_contents(pool, &found, expected, "raw_get");
+
+				if let (Some(hash), Some(found_hash)) = (pool.raw_precomputed_hash(&raw), pool.raw_precomputed_hash(&found)) {
+					assert_eq!(hash, found_hash, "precomputed hash of {s:?} found by raw_get differs");
+				}
+			}
+		}
+	}
+}
+
+/// Strings with the same contents have the same precomputed hash (if they
+/// both have one), and the hash survives cloning
+pub fn precomputed_hash<P: Pool>(pool: &P) {
+	for s in samples() {
+		let a = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		let b = unsafe { pool.raw_from_vec(s.clone().into_bytes()) };
+		let Some(hash) = pool.raw_precomputed_hash(&a) else { continue };
+
+		if let Some(other) = pool.raw_precomputed_hash(&b) {
+			assert_eq!(hash, other, "strings with the same contents ({s:?}) have different precomputed hashes");
+		}
+		if let Some(clone) = pool.raw_precomputed_hash(&pool.raw_clone(&a)) {
+			assert_eq!(hash, clone, "raw_clone changed the precomputed hash of {s:?}");
+		}
+	}
+}
+
+/// Raws created by a clone of the pool can be used with the original (and the
+/// other way around), even after the clone is dropped
+pub fn pool_clones<P: Pool>(pool: &P) {
+	for s in samples() {
+		let clone = pool.clone();
+		let raw = unsafe { clone.raw_from_slice(s.as_bytes()) };
+		let expected = contents(&clone, &raw).to_owned();
+		drop(clone);
+
+		assert_contents(pool, &raw, &expected, "raw created by a pool clone");
+		let raw = pool.raw_clone(&raw);
+
+		let clone = pool.clone();
+		assert_contents(&clone, &raw, &expected, "raw used by a pool clone");
+		assert_contents(&clone, &clone.raw_clone(&raw), &expected, "raw cloned by a pool clone");
+	}
+}
+
+/// Lots of strings can be interned and kept around at the same time, and all
+/// of them keep their contents, even in pools that evict, expire or run out of
+/// room for them
+pub fn many_strings<P: Pool>(pool: &P) {
+	let strings = (0..2000)
+		.map(|i| match i % 4 {
+			0 => { i.to_string() }
+			1 => { SAMPLES[i % SAMPLES.len()].to_owned() }
+			2 => { format!("{}{i}", "ü".repeat(i % 64)) }
+			_ => { format!("{i}").repeat(i % 16) }
+		})
+		.collect::<Vec<_>>();
+
+	let raws = strings.iter()
+		.map(|s| unsafe { pool.raw_from_slice(s.as_bytes()) })
+		.collect::<Vec<_>>();
+	let expected = raws.iter()
+		.map(|raw| contents(pool, raw).to_owned())
+		.collect::<Vec<_>>();
+
+	// and again, now that the pool is full(er)
+	for (s, expected) in strings.iter().zip(&expected) {
+		let raw = unsafe { pool.raw_from_slice(s.as_bytes()) };
+		assert_contents(pool, &raw, expected, "interning many strings");
+	}
+	for (raw, expected) in raws.iter().zip(&expected) {
+		assert_contents(pool, raw, expected, "keeping many strings");
+	}
+}
+
+/// Strings to check pools with: the samples the `arbitrary` and `proptest`
+/// generators use, plus some longer ones
+fn samples() -> Vec<StdString> {
+	SAMPLES.iter()
+		.map(|s| (*s).to_owned())
+		.chain([
+			"  Mixed Case  and  Whitespace ".to_owned(),
+			"a".repeat(100),
+			"日本語🦀é".repeat(200)
+		])
+		.collect()
+}
+
+fn contents<'r, P: Pool>(pool: &P, raw: &'r P::Raw) -> &'r str {
+	str::from_utf8(pool.raw_to_slice(raw))
+		.expect("raw contents to be valid UTF-8")
+}
+
+#[track_caller]
+fn assert_contents<P: Pool>(pool: &P, raw: &P::Raw, expected: &str, what: &str) {
+	assert_eq!(contents(pool, raw), expected, "{what} gave the wrong contents");
+}
+
+#[cfg(test)]
+#[path = "../tests/conformance.rs"]
+mod tests;
@@ -0,0 +1,91 @@
+use super::{ Pool, SlicesWrap };
+
+/// A pool that first looks strings up in a parent pool, and only if it isn't
+/// in there, interns it into a child pool. The parent is never inserted into.
+///
+/// This is meant for a large, shared parent vocabulary (for example a
+/// [`FrozenPool`](super::FrozenPool) of built-in names), with a separate child
+/// pool on top of it per user of it. Once the child pool and all strings from
+/// it are dropped, the strings that were only in the child are freed, while the
+/// parent is left alone.
+///
+/// The parent pool needs to support [`Pool::raw_get`], or else everything will
+/// end up in the child pool.
+#[derive(Clone, Debug)]
+pub struct FallbackPool<Parent, Child> {
+	parent: Parent,
+	child: Child
+}
+
+#[cfg(test)]
+#[path = "../tests/fallback_pool.rs"]
+mod tests;
+
+impl<Parent: Pool, Child: Pool> FallbackPool<Parent, Child> {
+	pub fn new(parent: Parent, child: Child) -> Self {
+		Self { parent, child }
+	}
+
+	pub fn parent(&self) -> &Parent {
+		&self.parent
+	}
+
+	pub fn child(&self) -> &Child {
+		&self.child
+	}
+}
+
+/// Raw value of a [`FallbackPool`]
+#[derive(Debug)]
+pub enum FallbackRaw<ParentRaw, ChildRaw> {
+	Parent(ParentRaw),
+	Child(ChildRaw)
+}
+
+impl<ParentRaw, ChildRaw> FallbackRaw<ParentRaw, ChildRaw> {
+	pub fn is_parent(&self) -> bool {
+		matches!(self, Self::Parent(_))
+	}
+}
+
+impl<Parent: Pool, Child: Pool> Pool for FallbackPool<Parent, Child> {
+	type Raw = FallbackRaw<Parent::Raw, Child::Raw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		match self.parent.raw_get(SlicesWrap(slices.0)) {
+			Some(raw) => { FallbackRaw::Parent(raw) }
+			None => { FallbackRaw::Child(self.child.raw_from_slices(slices)) }
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		match raw {
+			FallbackRaw::Parent(raw) => { self.parent.raw_to_slice(raw) }
+			FallbackRaw::Child(raw) => { self.child.raw_to_slice(raw) }
+		}
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		match self.parent.raw_get(SlicesWrap(slices.0)) {
+			Some(raw) => { Some(FallbackRaw::Parent(raw)) }
+			None => { self.child.raw_get(slices).map(FallbackRaw::Child) }
+		}
+	}
+
+	fn raw_into_vec(&self, raw: Self::Raw) -> Vec<u8> {
+		match raw {
+			FallbackRaw::Parent(raw) => { self.parent.raw_into_vec(raw) }
+			FallbackRaw::Child(raw) => { self.child.raw_into_vec(raw) }
+		}
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		match raw {
+			FallbackRaw::Parent(raw) => { FallbackRaw::Parent(self.parent.raw_clone(raw)) }
+			FallbackRaw::Child(raw) => { FallbackRaw::Child(self.child.raw_clone(raw)) }
+		}
+	}
+
+	// not implementing raw_precomputed_hash, since hashes from the parent and
+	// child pools can't be compared with each other
+}
@@ -0,0 +1,147 @@
+use super::{ Pool, SlicesWrap, SnapshotError };
+use super::snapshot;
+use super::global::SliceHashWrap;
+use ::hashbrown::HashMap;
+use ::hashbrown::hash_map::{ DefaultHashBuilder, RawEntryMut };
+use ::std::fmt::{ self, Debug };
+use ::std::hash::BuildHasher;
+use ::std::io::{ self, Read, Write };
+use ::std::sync::Arc;
+
+/// An immutable pool. Once created, nothing is ever inserted into it anymore,
+/// so looking strings up doesn't need any locking.
+///
+/// Create one by freezing an existing pool (see [`GlobalPool::freeze`](super::GlobalPool::freeze)),
+/// or from a list of strings using [`FrozenPool::new`].
+///
+/// Interning a string that isn't in the frozen pool still works, but it gets
+/// its own allocation that isn't shared with anything and isn't added to the
+/// pool. To find out whether a string is in the pool, use
+/// [`FrozenPool::get`] (or [`Pool::raw_get`]) instead.
+///
+/// Clones of a `FrozenPool` share the same storage.
+#[derive(Clone)]
+pub struct FrozenPool {
+	index: Arc<HashMap<Arc<SliceHashWrap>, ()>>
+}
+
+#[cfg(test)]
+#[path = "../tests/frozen_pool.rs"]
+mod tests;
+
+impl FrozenPool {
+	/// Creates a frozen pool containing the provided strings
+	pub fn new<I, S>(strings: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: AsRef<str>
+	{
+		let hash_builder = DefaultHashBuilder::default();
+		let raws = strings.into_iter()
+			.map(|s| Arc::new(SliceHashWrap::from_slices(&hash_builder, &SlicesWrap(&[s.as_ref().as_bytes()]))))
+			.collect::<Vec<_>>();
+
+		Self::from_raws(hash_builder, raws)
+	}
+
+	/// Creates a frozen pool containing the strings in a snapshot. See
+	/// [`GlobalPool::load_snapshot`](super::GlobalPool::load_snapshot).
+	pub fn load_snapshot<R: Read>(reader: R) -> Result<Self, SnapshotError> {
+		let hash_builder = DefaultHashBuilder::default();
+		let mut raws = Vec::new();
+
+		snapshot::read(reader, |s| {
+			raws.push(Arc::new(SliceHashWrap::from_slices(&hash_builder, &SlicesWrap(&[s.as_bytes()]))));
+		})?;
+
+		Ok(Self::from_raws(hash_builder, raws))
+	}
+
+	/// Writes every string in the pool to `writer`. See
+	/// [`GlobalPool::save_snapshot`](super::GlobalPool::save_snapshot).
+	pub fn save_snapshot<W: Write>(&self, writer: W) -> io::Result<()> {
+		snapshot::write(writer, self.index.keys().map(|raw| raw.as_bytes()))
+	}
+
+	/// Creates a frozen pool from raws that were hashed using `hash_builder`
+	pub(crate) fn from_raws<I>(hash_builder: DefaultHashBuilder, raws: I) -> Self
+	where
+		I: IntoIterator<Item = Arc<SliceHashWrap>>
+	{
+		let raws = raws.into_iter();
+		let mut index = HashMap::with_capacity_and_hasher(raws.size_hint().0, hash_builder);
+
+		for raw in raws {
+			let hash = raw.precomputed_hash();
+			let entry = index.raw_entry_mut().from_hash(hash, |key: &Arc<SliceHashWrap>| **key == *raw);
+
+			if let RawEntryMut::Vacant(entry) = entry {
+				entry.insert_with_hasher(hash, raw, (), |raw| raw.precomputed_hash());
+			}
+		}
+
+		Self { index: Arc::new(index) }
+	}
+
+	/// Number of strings in the pool
+	pub fn len(&self) -> usize {
+		self.index.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.index.is_empty()
+	}
+
+	/// Looks up a string in the pool, returning `None` if it isn't in there
+	pub fn get(&self, s: &str) -> Option<crate::String<Self>> {
+		let raw = self.raw_get(SlicesWrap(&[s.as_bytes()]))?;
+		Some(unsafe { crate::String::from_raw_in(raw, self.clone()) })
+	}
+
+	/// Iterates through every string in the pool, in no particular order
+	pub fn iter(&self) -> impl Iterator<Item = crate::String<Self>> + '_ {
+		self.index.keys()
+			.map(|raw| unsafe { crate::String::from_raw_in(Arc::clone(raw), self.clone()) })
+	}
+}
+
+impl Debug for FrozenPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("FrozenPool")
+			.field("len", &self.index.len())
+			.finish()
+	}
+}
+
+impl Pool for FrozenPool {
+	type Raw = Arc<SliceHashWrap>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let hash = self.index.hasher().hash_one(&slices);
+
+		match self.index.raw_entry().from_hash(hash, |raw| raw.matches(hash, &slices)) {
+			Some((raw, _)) => { Arc::clone(raw) }
+			None => { Arc::new(SliceHashWrap::new(hash, slices.to_boxed_slice())) }
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw.as_bytes()
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		let hash = self.index.hasher().hash_one(&slices);
+
+		self.index.raw_entry()
+			.from_hash(hash, |raw| raw.matches(hash, &slices))
+			.map(|(raw, _)| Arc::clone(raw))
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		Arc::clone(raw)
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		Some(raw.precomputed_hash())
+	}
+}
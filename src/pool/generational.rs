@@ -0,0 +1,104 @@
+use super::{ Pool, SlicesWrap };
+use super::global::SliceHashWrap;
+use super::index::{ self, Index };
+use ::parking_lot::Mutex;
+use ::std::fmt::{ self, Debug };
+use ::std::sync::Arc;
+
+/// A pool that tags every string with the generation it was last interned in.
+/// Calling [`advance`](GenerationalPool::advance) starts a new generation,
+/// dropping strings from the index that haven't been interned in the last
+/// `keep` generations.
+///
+/// Clones of a `GenerationalPool` share the same index.
+#[derive(Clone)]
+pub struct GenerationalPool {
+	inner: Arc<Mutex<GenerationalInner>>
+}
+
+#[cfg(test)]
+#[path = "../tests/generational_pool.rs"]
+mod tests;
+
+struct GenerationalInner {
+	keep: u64,
+	generation: u64,
+	/// entries, with the generation they were last interned in
+	index: Index<u64>
+}
+
+impl GenerationalPool {
+	/// Creates a pool that keeps strings from the current generation, plus
+	/// `keep` generations before it.
+	pub fn new(keep: u64) -> Self {
+		let inner = GenerationalInner { keep, generation: 0, index: Index::new() };
+		Self { inner: Arc::new(Mutex::new(inner)) }
+	}
+
+	/// The current generation
+	pub fn generation(&self) -> u64 {
+		self.inner.lock().generation
+	}
+
+	/// Number of generations before the current one that are kept
+	pub fn keep(&self) -> u64 {
+		self.inner.lock().keep
+	}
+
+	/// Number of strings currently in the index
+	pub fn len(&self) -> usize {
+		self.inner.lock().index.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Starts a new generation, removing strings from the index that were last
+	/// interned more than `keep` generations before it. Returns how many
+	/// were removed.
+	///
+	/// Strings that are still in use stay in the index, and will be removed
+	/// by a later call once they aren't in use anymore (as long as they don't
+	/// get interned again in the meantime).
+	pub fn advance(&self) -> usize {
+		let mut inner = self.inner.lock();
+		let GenerationalInner { keep, generation, index } = &mut *inner;
+
+		*generation += 1;
+		index.remove_unused(|last| *generation - *last > *keep)
+	}
+}
+
+impl Debug for GenerationalPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let inner = self.inner.lock();
+		f.debug_struct("GenerationalPool")
+			.field("len", &inner.index.len())
+			.field("generation", &inner.generation)
+			.field("keep", &inner.keep)
+			.finish()
+	}
+}
+
+impl Pool for GenerationalPool {
+	type Raw = Arc<SliceHashWrap>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let mut inner = self.inner.lock();
+		let GenerationalInner { generation, index, .. } = &mut *inner;
+
+		let hash = index.hash(&slices);
+		let (raw, last, _) = index.get_or_insert_with(hash, &slices, || *generation);
+		*last = *generation;
+
+		Arc::clone(raw)
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		// looking up doesn't count as interning, so doesn't update the generation
+		self.inner.lock().index.get_raw(&slices)
+	}
+
+	index::impl_index_raw!();
+}
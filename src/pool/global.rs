@@ -1,103 +1,249 @@
-use super::{ Pool, SlicesWrap };
-use ::hashbrown::{ Equivalent, HashSet };
+use super::{ FrozenPool, Pool, SlicesWrap, SnapshotError };
+use super::hash_joined;
+use super::snapshot;
+#[cfg(feature = "lock-free")]
+use super::lock_free::LockFreeSet;
+#[cfg(feature = "thread-cache")]
+use super::thread_cache;
+use ::hashbrown::{ Equivalent, HashMap };
+use ::hashbrown::hash_map::{ DefaultHashBuilder, RawEntryMut };
 use ::lazy_wrap::LazyWrap;
 use ::parking_lot::RwLock;
-use ::std::hash::{ Hash, Hasher };
+use ::std::hash::{ BuildHasher, Hash, Hasher };
+use ::std::io::{ self, Read, Write };
 use ::std::sync::Arc;
 
 /// The default, global string pool
-#[derive(Clone, Debug)]
+///
+/// By default it is backed by a hash set behind a `RwLock`. Enabling the
+/// `lock-free` feature switches it to a lock-free hash trie instead, which
+/// doesn't block when interning from many threads at once.
+///
+/// Enabling the `thread-cache` feature puts a small per-thread cache in front
+/// of it, so interning or looking up recently used strings again on the same
+/// thread doesn't have to touch the shared set at all.
+#[derive(Clone, Debug, Default)]
 pub struct GlobalPool;
 
 #[cfg(test)]
 #[path = "../tests/global_pool.rs"]
 mod tests;
 
+impl GlobalPool {
+	/// Looks up a string in the pool, returning it if it is already interned,
+	/// without inserting it if it isn't.
+	pub fn get(&self, s: &str) -> Option<crate::String> {
+		let raw = self.raw_get(SlicesWrap(&[s.as_bytes()]))?;
+		Some(unsafe { crate::String::from_raw_in(raw, GlobalPool) })
+	}
+
+	/// Looks up a string in the pool, calling `f` with its raw value if it is
+	/// already interned. Unlike [`raw_get`](Pool::raw_get), the raw value is
+	/// only borrowed, so this doesn't have to clone the `Arc` (which is two
+	/// atomic operations, once for the clone and once for the drop).
+	pub(crate) fn with_raw<T, F>(&self, slices: SlicesWrap, f: F) -> Option<T>
+	where
+		F: FnOnce(&Arc<SliceHashWrap>) -> T
+	{
+		let hash = POOL.hash_builder().hash_one(&slices);
+		POOL.with_hashed(hash, &slices, f)
+	}
+
+	/// Creates a [`FrozenPool`] containing every string currently in the
+	/// global pool. The strings are shared, not copied, so strings from the
+	/// frozen pool share their allocation with the same strings from the
+	/// global pool.
+	pub fn freeze(&self) -> FrozenPool {
+		FrozenPool::from_raws(POOL.hash_builder().clone(), POOL.raws())
+	}
+
+	/// Writes every string currently in the global pool to `writer`. Load it
+	/// again using [`load_snapshot`](Self::load_snapshot).
+	///
+	/// A snapshot is laid out as follows, with all fixed size integers little
+	/// endian, and varints LEB128 encoded:
+	///
+	/// - magic bytes `b"strpool\0"`
+	/// - format version, as a `u32` (currently 1)
+	/// - number of entries, as a varint
+	/// - for every entry, its length in bytes as a varint, followed by the bytes
+	/// - 64-bit FNV-1a checksum of everything before it, as a `u64`
+	pub fn save_snapshot<W: Write>(&self, writer: W) -> io::Result<()> {
+		let raws = POOL.raws();
+		snapshot::write(writer, raws.iter().map(|raw| raw.as_bytes()))
+	}
+
+	/// Reads a snapshot written by [`save_snapshot`](Self::save_snapshot)
+	/// (of this or another pool), interning every string in it. Returns the
+	/// number of strings in the snapshot.
+	///
+	/// The snapshot is fully validated before anything is interned, so if it
+	/// is corrupted, an error is returned and the pool is left untouched.
+	pub fn load_snapshot<R: Read>(&self, reader: R) -> Result<usize, SnapshotError> {
+		snapshot::read(reader, |s| {
+			let _ = unsafe { self.raw_from_slice(s.as_bytes()) };
+		})
+	}
+}
+
 /// The actual backing store for the default global pool
-static POOL: LazyWrap<RwLock<HashSet<<GlobalPool as Pool>::Raw>>> = LazyWrap::new(|| {
-	let set = HashSet::new();
-	RwLock::new(set)
-});
+#[cfg(not(feature = "lock-free"))]
+static POOL: LazyWrap<LockedSet> = LazyWrap::new(LockedSet::new);
+
+/// The actual backing store for the default global pool
+#[cfg(feature = "lock-free")]
+static POOL: LazyWrap<LockFreeSet> = LazyWrap::new(LockFreeSet::new);
 
 impl Pool for GlobalPool {
 	type Raw = Arc<SliceHashWrap>;
 
 	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
-		let pool = POOL.read();
-
-		if let Some(raw) = pool.get(&slices) {
-			let raw = Arc::clone(raw);
-			drop(pool);
-			raw
-		} else {
-			drop(pool);
-
-			let mut pool = POOL.write();
-			let raw = pool.get_or_insert_with(&slices, |slices| {
-				Arc::new(SliceHashWrap(slices.to_boxed_slice()))
-			});
-
-			let raw = Arc::clone(raw);
-			drop(pool);
-			raw
-		}
+		let hash = POOL.hash_builder().hash_one(&slices);
+
+		#[cfg(feature = "thread-cache")]
+		let raw = thread_cache::get_or_insert_with(hash, &slices, || Some(POOL.intern_hashed(hash, &slices)))
+			.expect("interning always returns a raw");
+		#[cfg(not(feature = "thread-cache"))]
+		let raw = POOL.intern_hashed(hash, &slices);
+
+		raw
 	}
 
 	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
-		&raw.0
+		&raw.bytes
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		let hash = POOL.hash_builder().hash_one(&slices);
+
+		#[cfg(feature = "thread-cache")]
+		let raw = thread_cache::get_or_insert_with(hash, &slices, || POOL.get_hashed(hash, &slices));
+		#[cfg(not(feature = "thread-cache"))]
+		let raw = POOL.get_hashed(hash, &slices);
+
+		raw
 	}
 
 	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
 		Arc::clone(raw)
 	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		Some(raw.hash)
+	}
+}
+
+/// Default backing store for the global pool, a hash set behind a `RwLock`.
+/// With the `lock-free` feature, `LockFreeSet` is used instead.
+#[cfg(not(feature = "lock-free"))]
+struct LockedSet {
+	hash_builder: DefaultHashBuilder,
+	set: RwLock<HashMap<Arc<SliceHashWrap>, ()>>
+}
+
+#[cfg(not(feature = "lock-free"))]
+impl LockedSet {
+	fn new() -> Self {
+		let hash_builder = DefaultHashBuilder::default();
+		let set = RwLock::new(HashMap::with_hasher(hash_builder.clone()));
+		Self { hash_builder, set }
+	}
+
+	fn hash_builder(&self) -> &DefaultHashBuilder {
+		&self.hash_builder
+	}
+
+	fn get_hashed(&self, hash: u64, slices: &SlicesWrap) -> Option<Arc<SliceHashWrap>> {
+		self.with_hashed(hash, slices, Arc::clone)
+	}
+
+	/// Calls `f` with the entry while holding the read lock
+	fn with_hashed<T, F>(&self, hash: u64, slices: &SlicesWrap, f: F) -> Option<T>
+	where
+		F: FnOnce(&Arc<SliceHashWrap>) -> T
+	{
+		self.set.read()
+			.raw_entry()
+			.from_hash(hash, |raw| raw.matches(hash, slices))
+			.map(|(raw, _)| f(raw))
+	}
+
+	fn intern_hashed(&self, hash: u64, slices: &SlicesWrap) -> Arc<SliceHashWrap> {
+		let set = self.set.read();
+		if let Some((raw, _)) = set.raw_entry().from_hash(hash, |raw| raw.matches(hash, slices)) {
+			return Arc::clone(raw)
+		}
+		drop(set);
+
+		let mut set = self.set.write();
+		match set.raw_entry_mut().from_hash(hash, |raw| raw.matches(hash, slices)) {
+			RawEntryMut::Occupied(entry) => { Arc::clone(entry.key()) }
+			RawEntryMut::Vacant(entry) => {
+				let raw = Arc::new(SliceHashWrap::new(hash, slices.to_boxed_slice()));
+				// passing our own hasher, so when the set has to grow, it
+				// uses the hash we already have instead of rehashing every byte
+				let (raw, _) = entry.insert_with_hasher(hash, raw, (), |raw| raw.hash);
+				Arc::clone(raw)
+			}
+		}
+	}
+
+	fn raws(&self) -> Vec<Arc<SliceHashWrap>> {
+		self.set.read().keys().cloned().collect()
+	}
 }
 
-/// Wrapper for `Box<[u8]>` that hashes the slice within by repeatedly
-/// calling `Hasher::write_u8`, matching [`Hash`] impl of [`SlicesWrap`]
+/// Wrapper for `Box<[u8]>` that hashes the slice within the same way as
+/// the [`Hash`] impl of [`SlicesWrap`] does.
+///
+/// It also stores the hash the pool computed when the string was interned, so
+/// the pool never has to hash the bytes again.
 #[derive(Debug)]
-#[repr(transparent)]
-pub struct SliceHashWrap(Box<[u8]>);
+pub struct SliceHashWrap {
+	hash: u64,
+	bytes: Box<[u8]>
+}
+
+impl SliceHashWrap {
+	pub(crate) fn new(hash: u64, bytes: Box<[u8]>) -> Self {
+		Self { hash, bytes }
+	}
+
+	pub(crate) fn from_slices(hash_builder: &DefaultHashBuilder, slices: &SlicesWrap) -> Self {
+		Self::new(hash_builder.hash_one(slices), slices.to_boxed_slice())
+	}
+
+	/// The hash computed for this slice when it was interned
+	pub fn precomputed_hash(&self) -> u64 {
+		self.hash
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+
+	/// Compares hashes first, only comparing the bytes if they match
+	pub(crate) fn matches(&self, hash: u64, slices: &SlicesWrap) -> bool {
+		self.hash == hash && slices.eq_bytes(&self.bytes)
+	}
+}
 
 impl Hash for SliceHashWrap {
 	fn hash<H: Hasher>(&self, state: &mut H) {
-		self.0.iter().copied()
-			.for_each(|b| state.write_u8(b));
+		hash_joined(&[&self.bytes], state)
 	}
 }
 
 impl PartialEq for SliceHashWrap {
 	fn eq(&self, other: &Self) -> bool {
-		*self.0 == *other.0
+		self.hash == other.hash && *self.bytes == *other.bytes
 	}
 }
 
 impl Eq for SliceHashWrap {}
 
-impl<'h> Equivalent<<GlobalPool as Pool>::Raw> for SlicesWrap<'h> {
-	fn equivalent(&self, key: &<GlobalPool as Pool>::Raw) -> bool {
-		let mut iter1 = key.0.iter().copied();
-		let mut iter2 = self.into_iter();
-
-		loop {
-			match (iter1.next(), iter2.next()) {
-				// Some Some
-				//    - if a == b, good but not done yet, continue
-				(Some(a), Some(b)) if a == b => { continue }
-				//    - else (a != b), not good, return false
-				(Some(a), Some(b)) => { return false }
-
-				// Some None
-				// None Some
-				//    - iters are of different lengths
-				//    - return false
-				(Some(_), None) | (None, Some(_)) => { return false }
-
-				// None None
-				//    - if we haven't returned yet, all bytes in iter are equal
-				//    - both iters are also same length
-				//    - return true
-				(None, None) => { return true }
-			}
-		}
+impl<'h> Equivalent<SliceHashWrap> for SlicesWrap<'h> {
+	fn equivalent(&self, key: &SliceHashWrap) -> bool {
+		self.eq_bytes(&key.bytes)
 	}
 }
@@ -0,0 +1,106 @@
+use super::SlicesWrap;
+use super::global::SliceHashWrap;
+use ::hashbrown::HashMap;
+use ::hashbrown::hash_map::RawEntryMut;
+use ::std::hash::BuildHasher;
+use ::std::sync::Arc;
+
+/// Index of interned strings, storing some metadata alongside every entry.
+/// Shared by the pools that need to keep track of something per entry to decide
+/// when to drop it from their index.
+///
+/// Like [`GlobalPool`](super::GlobalPool), entries are hashed once when they
+/// are inserted, and that hash is reused when the index has to grow.
+///
+/// Pools keep their index behind a lock, and only hand out new handles to an
+/// entry while holding it. So while the lock is held, an entry that's only
+/// referenced by the index stays that way, and can be removed without racing
+/// with someone interning it again.
+pub(crate) struct Index<M> {
+	map: HashMap<Arc<SliceHashWrap>, M>
+}
+
+impl<M> Index<M> {
+	pub(crate) fn new() -> Self {
+		Self { map: HashMap::new() }
+	}
+
+	pub(crate) fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	pub(crate) fn hash(&self, slices: &SlicesWrap) -> u64 {
+		self.map.hasher().hash_one(slices)
+	}
+
+	pub(crate) fn get(&self, hash: u64, slices: &SlicesWrap) -> Option<(&Arc<SliceHashWrap>, &M)> {
+		self.map.raw_entry().from_hash(hash, |raw| raw.matches(hash, slices))
+	}
+
+	/// Looks up `slices`, returning a new handle to it without touching its
+	/// metadata. For implementing [`Pool::raw_get`](super::Pool::raw_get).
+	pub(crate) fn get_raw(&self, slices: &SlicesWrap) -> Option<Arc<SliceHashWrap>> {
+		let hash = self.hash(slices);
+		self.get(hash, slices).map(|(raw, _)| Arc::clone(raw))
+	}
+
+	/// Gets the entry for `slices`, inserting it with metadata from `meta` if
+	/// it doesn't exist yet. Also returns whether it was inserted.
+	pub(crate) fn get_or_insert_with(
+		&mut self,
+		hash: u64,
+		slices: &SlicesWrap,
+		meta: impl FnOnce() -> M
+	) -> (&Arc<SliceHashWrap>, &mut M, bool) {
+		match self.map.raw_entry_mut().from_hash(hash, |raw| raw.matches(hash, slices)) {
+			RawEntryMut::Occupied(entry) => {
+				let (raw, meta) = entry.into_key_value();
+				(raw, meta, false)
+			}
+			RawEntryMut::Vacant(entry) => {
+				let raw = Arc::new(SliceHashWrap::new(hash, slices.to_boxed_slice()));
+				let (raw, meta) = entry.insert_with_hasher(hash, raw, meta(), |raw| raw.precomputed_hash());
+				(raw, meta, true)
+			}
+		}
+	}
+
+	/// Removes the entry for `raw` (by pointer), returning its metadata
+	pub(crate) fn remove(&mut self, raw: &Arc<SliceHashWrap>) -> Option<M> {
+		let entry = self.map.raw_entry_mut()
+			.from_hash(raw.precomputed_hash(), |key| Arc::ptr_eq(key, raw));
+
+		match entry {
+			RawEntryMut::Occupied(entry) => { Some(entry.remove()) }
+			RawEntryMut::Vacant(_) => { None }
+		}
+	}
+
+	/// Removes entries that `expired` returns true for, unless they're still
+	/// referenced by something other than the index. Returns how many were
+	/// removed.
+	pub(crate) fn remove_unused(&mut self, mut expired: impl FnMut(&M) -> bool) -> usize {
+		let len = self.map.len();
+		self.map.retain(|raw, meta| !expired(meta) || Arc::strong_count(raw) > 1);
+		len - self.map.len()
+	}
+}
+
+/// Implements the [`Pool`](super::Pool) functions that only need the raw
+/// value, for pools with [`Index`] entries as their raw values
+macro_rules! impl_index_raw {
+	() => {
+		fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+			raw.as_bytes()
+		}
+
+		fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+			Arc::clone(raw)
+		}
+
+		fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+			Some(raw.precomputed_hash())
+		}
+	};
+}
+pub(crate) use impl_index_raw;
@@ -0,0 +1,282 @@
+//! Lock-free backing store for [`GlobalPool`](super::GlobalPool), enabled with
+//! the `lock-free` feature.
+//!
+//! The set is a hash trie where every node holds (at most) one entry, and up to
+//! 16 children, indexed by the next 4 bits of the entry's hash. Once a node's
+//! entry is set, it never changes again, so inserting and looking up only ever
+//! needs atomic loads, plus a compare-and-swap to fill an empty slot.
+//!
+//! Most nodes only have a few children, so instead of a fixed array of 16
+//! pointers, a node's children are kept in a compact array with only the
+//! children it has. Adding a child replaces that array with a copy that has the
+//! new child in it. Other threads may still be reading the old array, so it is
+//! only freed once they're done with it, using epoch based reclamation
+//! (`crossbeam-epoch`). Every operation on the set pins the current thread
+//! for as long as it's walking the trie.
+//!
+//! The global pool never removes entries, so nodes and entries themselves are
+//! never unlinked from the trie while it's in use, only the children arrays
+//! are. Nodes and entries are freed when the set itself is dropped.
+
+use super::SlicesWrap;
+use super::global::SliceHashWrap;
+use ::crossbeam_epoch::{ self as epoch, Atomic, Guard, Owned };
+use ::hashbrown::hash_map::DefaultHashBuilder;
+use ::std::hash::BuildHasher;
+use ::std::mem::ManuallyDrop;
+use ::std::ptr;
+use ::std::sync::Arc;
+use ::std::sync::atomic::{ AtomicPtr, Ordering };
+
+const BITS: u32 = 4;
+const FANOUT: usize = 1 << BITS;
+
+#[cfg(test)]
+#[path = "../tests/lock_free.rs"]
+mod tests;
+
+pub(crate) struct LockFreeSet {
+	hash_builder: DefaultHashBuilder,
+	root: Node
+}
+
+struct Node {
+	/// Pointer obtained from [`Arc::into_raw`], holding one strong count
+	entry: AtomicPtr<SliceHashWrap>,
+	/// Null until the node gets its first child
+	children: Atomic<Children>
+}
+
+/// The children a node has so far. `mask` has a bit set for every child index
+/// that has a child, and `nodes` has those children, in order of their index.
+/// Never changed once published, only replaced.
+struct Children {
+	mask: u16,
+	/// Owned by the set, not by this array, since they're shared with the
+	/// arrays that replace it
+	nodes: Box<[*mut Node]>
+}
+
+// SAFETY: the nodes are only ever accessed through shared references, and are
+// owned by the set, which is Send and Sync itself
+unsafe impl Send for Children {}
+unsafe impl Sync for Children {}
+
+impl LockFreeSet {
+	pub(crate) fn new() -> Self {
+		Self {
+			hash_builder: DefaultHashBuilder::default(),
+			root: Node::new()
+		}
+	}
+
+	pub(crate) fn hash_builder(&self) -> &DefaultHashBuilder {
+		&self.hash_builder
+	}
+
+	pub(crate) fn get_hashed(&self, hash: u64, slices: &SlicesWrap) -> Option<Arc<SliceHashWrap>> {
+		self.with_hashed(hash, slices, Arc::clone)
+	}
+
+	/// Calls `f` with the entry, without cloning it out of the set
+	pub(crate) fn with_hashed<T, F>(&self, hash: u64, slices: &SlicesWrap, f: F) -> Option<T>
+	where
+		F: FnOnce(&Arc<SliceHashWrap>) -> T
+	{
+		let guard = &epoch::pin();
+		let mut node = &self.root;
+		let mut level = 0;
+
+		loop {
+			let entry = node.entry.load(Ordering::Acquire);
+			if entry.is_null() { return None }
+
+			// SAFETY: entries are never removed while the set is alive
+			if unsafe { &*entry }.matches(hash, slices) {
+				// the set's own strong count, borrowed, not to be dropped
+				let entry = ManuallyDrop::new(unsafe { Arc::from_raw(entry) });
+				return Some(f(&entry))
+			}
+
+			node = node.child(index(hash, level), guard)?;
+			level += 1;
+		}
+	}
+
+	/// Every intern of the same bytes walks the same path down the trie, and
+	/// stops at the first node that is either empty or has those bytes. Since
+	/// filled slots never change, whichever thread fills that node first wins,
+	/// and everyone else ends up returning its entry.
+	pub(crate) fn intern_hashed(&self, hash: u64, slices: &SlicesWrap) -> Arc<SliceHashWrap> {
+		let guard = &epoch::pin();
+		let mut node = &self.root;
+		let mut level = 0;
+		let mut new_entry = ptr::null_mut::<SliceHashWrap>();
+
+		loop {
+			let mut entry = node.entry.load(Ordering::Acquire);
+
+			if entry.is_null() {
+				if new_entry.is_null() {
+					let raw = Arc::new(SliceHashWrap::new(hash, slices.to_boxed_slice()));
+					new_entry = Arc::into_raw(raw) as *mut _;
+				}
+
+				match node.entry.compare_exchange(ptr::null_mut(), new_entry, Ordering::AcqRel, Ordering::Acquire) {
+					Ok(_) => { return unsafe { clone_entry(&*new_entry) } }
+					Err(current) => { entry = current }
+				}
+			}
+
+			let entry = unsafe { &*entry };
+			if entry.matches(hash, slices) {
+				if !new_entry.is_null() {
+					// never published, we still own it
+					drop(unsafe { Arc::from_raw(new_entry) });
+				}
+				return unsafe { clone_entry(entry) }
+			}
+
+			node = node.child_or_insert(index(hash, level), guard);
+			level += 1;
+		}
+	}
+
+	/// Clones out every entry currently in the set, in no particular order
+	pub(crate) fn raws(&self) -> Vec<Arc<SliceHashWrap>> {
+		let guard = &epoch::pin();
+		let mut raws = Vec::new();
+		let mut stack = vec![&self.root];
+
+		while let Some(node) = stack.pop() {
+			let entry = node.entry.load(Ordering::Acquire);
+			if entry.is_null() { continue }
+			raws.push(unsafe { clone_entry(&*entry) });
+
+			if let Some(children) = node.children(guard) {
+				stack.extend(children.nodes.iter().map(|&child| unsafe { &*child }));
+			}
+		}
+
+		raws
+	}
+}
+
+impl Drop for LockFreeSet {
+	fn drop(&mut self) {
+		// the root isn't boxed, so its children are freed here and the
+		// rest recursively (iteratively) from there
+		let mut stack = Vec::new();
+		self.root.take_into(&mut stack);
+
+		while let Some(node) = stack.pop() {
+			let mut node = unsafe { Box::from_raw(node) };
+			node.take_into(&mut stack);
+		}
+	}
+}
+
+impl Node {
+	fn new() -> Self {
+		Self {
+			entry: AtomicPtr::new(ptr::null_mut()),
+			children: Atomic::null()
+		}
+	}
+
+	fn children<'g>(&self, guard: &'g Guard) -> Option<&'g Children> {
+		// SAFETY: replaced arrays are only freed once every thread that could
+		// have loaded them is unpinned
+		unsafe { self.children.load(Ordering::Acquire, guard).as_ref() }
+	}
+
+	/// Nodes live as long as the set, so the returned node can outlive `guard`
+	fn child(&self, i: usize, guard: &Guard) -> Option<&Node> {
+		let child = self.children(guard)?.get(i)?;
+		Some(unsafe { &*child })
+	}
+
+	fn child_or_insert(&self, i: usize, guard: &Guard) -> &Node {
+		let mut new_child = ptr::null_mut::<Node>();
+
+		loop {
+			let current = self.children.load(Ordering::Acquire, guard);
+			let children = unsafe { current.as_ref() };
+
+			if let Some(child) = children.and_then(|children| children.get(i)) {
+				if !new_child.is_null() {
+					// someone else got there first, ours was never published
+					drop(unsafe { Box::from_raw(new_child) });
+				}
+				return unsafe { &*child }
+			}
+
+			if new_child.is_null() { new_child = Box::into_raw(Box::new(Node::new())) }
+			let replacement = Owned::new(Children::with_child(children, i, new_child));
+
+			// on failure, some other child was added in the meantime, so
+			// try again on top of that
+			if self.children.compare_exchange(current, replacement, Ordering::AcqRel, Ordering::Acquire, guard).is_ok() {
+				if !current.is_null() {
+					// SAFETY: just unlinked, and it doesn't own the nodes in it
+					unsafe { guard.defer_destroy(current) }
+				}
+				return unsafe { &*new_child }
+			}
+		}
+	}
+
+	/// Releases this node's entry and children array, and pushes its children
+	/// onto `stack`
+	fn take_into(&mut self, stack: &mut Vec<*mut Node>) {
+		let entry = *self.entry.get_mut();
+		if !entry.is_null() { drop(unsafe { Arc::from_raw(entry) }) }
+
+		// SAFETY: we have exclusive access, so nobody else can be reading it
+		let children = unsafe { self.children.load(Ordering::Relaxed, epoch::unprotected()) };
+		if !children.is_null() {
+			let children = unsafe { children.into_owned() };
+			stack.extend(children.nodes.iter().copied());
+		}
+	}
+}
+
+impl Children {
+	fn get(&self, i: usize) -> Option<*mut Node> {
+		let bit = 1 << i;
+		if self.mask & bit == 0 { return None }
+		Some(self.nodes[(self.mask & (bit - 1)).count_ones() as usize])
+	}
+
+	/// Copy of `children` (if there are any yet) with `child` added at `i`
+	fn with_child(children: Option<&Children>, i: usize, child: *mut Node) -> Self {
+		let (mask, nodes) = children
+			.map(|children| (children.mask, &*children.nodes))
+			.unwrap_or((0, &[]));
+
+		let bit = 1 << i;
+		let at = (mask & (bit - 1)).count_ones() as usize;
+
+		let mut new_nodes = Vec::with_capacity(nodes.len() + 1);
+		new_nodes.extend_from_slice(&nodes[..at]);
+		new_nodes.push(child);
+		new_nodes.extend_from_slice(&nodes[at..]);
+
+		Self { mask: mask | bit, nodes: new_nodes.into_boxed_slice() }
+	}
+}
+
+/// Picks the child for `level`. After all 64 bits are used up, it starts over
+/// from the beginning; only entries with fully colliding hashes get that deep
+fn index(hash: u64, level: u32) -> usize {
+	(hash.rotate_right((level * BITS) % u64::BITS) as usize) & (FANOUT - 1)
+}
+
+/// # Safety
+///
+/// `entry` must point to an entry stored in the set, which holds a strong count
+unsafe fn clone_entry(entry: &SliceHashWrap) -> Arc<SliceHashWrap> {
+	let entry = entry as *const SliceHashWrap;
+	Arc::increment_strong_count(entry);
+	Arc::from_raw(entry)
+}
@@ -0,0 +1,108 @@
+use super::{ Pool, SlicesWrap };
+use super::global::SliceHashWrap;
+use super::index::{ self, Index };
+use ::parking_lot::Mutex;
+use ::std::collections::BTreeMap;
+use ::std::fmt::{ self, Debug };
+use ::std::sync::Arc;
+
+/// A pool that keeps at most a fixed number of strings in its index, evicting
+/// the least recently interned string when it's full.
+///
+/// Evicting a string only removes it from the index; its bytes stay alive as
+/// long as there are [`String`](crate::String)s still using it. Interning the
+/// same string after it has been evicted creates a new copy of it, so strings
+/// that are equal are not guaranteed to share an allocation like they are in
+/// [`GlobalPool`](super::GlobalPool).
+///
+/// Clones of an `LruPool` share the same index.
+#[derive(Clone)]
+pub struct LruPool {
+	inner: Arc<Mutex<LruInner>>
+}
+
+#[cfg(test)]
+#[path = "../tests/lru_pool.rs"]
+mod tests;
+
+struct LruInner {
+	capacity: usize,
+	/// entries, with the stamp of when they were last interned
+	index: Index<u64>,
+	/// entries ordered by when they were last interned, oldest first
+	order: BTreeMap<u64, Arc<SliceHashWrap>>,
+	next_stamp: u64
+}
+
+impl LruPool {
+	pub fn new(capacity: usize) -> Self {
+		let inner = LruInner {
+			capacity,
+			index: Index::new(),
+			order: BTreeMap::new(),
+			next_stamp: 0
+		};
+		Self { inner: Arc::new(Mutex::new(inner)) }
+	}
+
+	/// Maximum number of strings kept in the index
+	pub fn capacity(&self) -> usize {
+		self.inner.lock().capacity
+	}
+
+	/// Number of strings currently in the index
+	pub fn len(&self) -> usize {
+		self.inner.lock().index.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
+
+impl Debug for LruPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let inner = self.inner.lock();
+		f.debug_struct("LruPool")
+			.field("len", &inner.index.len())
+			.field("capacity", &inner.capacity)
+			.finish()
+	}
+}
+
+impl Pool for LruPool {
+	type Raw = Arc<SliceHashWrap>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let mut inner = self.inner.lock();
+		let LruInner { capacity, index, order, next_stamp } = &mut *inner;
+
+		let stamp = *next_stamp;
+		*next_stamp += 1;
+
+		let hash = index.hash(&slices);
+		let (raw, last_stamp, inserted) = index.get_or_insert_with(hash, &slices, || stamp);
+		let raw = Arc::clone(raw);
+
+		if !inserted {
+			order.remove(last_stamp);
+			*last_stamp = stamp;
+		}
+		order.insert(stamp, Arc::clone(&raw));
+
+		if index.len() > *capacity {
+			// there's at least one, the one we just inserted
+			let (_, oldest) = order.pop_first().unwrap();
+			index.remove(&oldest);
+		}
+
+		raw
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		// looking up doesn't count as interning, so doesn't update the order
+		self.inner.lock().index.get_raw(&slices)
+	}
+
+	index::impl_index_raw!();
+}
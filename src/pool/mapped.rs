@@ -0,0 +1,358 @@
+use super::{ Pool, PooledOrOwned, SlicesWrap, SnapshotError };
+use super::snapshot::fnv;
+use ::hashbrown::HashMap;
+use ::memmap2::Mmap;
+use ::std::fmt::{ self, Debug };
+use ::std::fs::File;
+use ::std::io::{ self, Write };
+use ::std::path::Path;
+use ::std::str;
+use ::std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"strpmap\0";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 64;
+const INDEX_ENTRY_LEN: usize = 24;
+const EMPTY: u32 = u32::MAX;
+
+/// A read-only pool, serving strings straight out of a memory-mapped table
+/// file written by [`MappedPoolBuilder`]. Strings in the table point directly
+/// into the mapping, without copying or allocating anything.
+///
+/// Interning a string that isn't in the table still works, but it gets its own
+/// (heap) allocation that isn't shared with anything. To intern those into
+/// another pool instead, use this as the parent of a [`FallbackPool`](super::FallbackPool).
+///
+/// Clones of a `MappedPool` share the same mapping, which is unmapped once the
+/// pool and every string pointing into it is dropped.
+///
+/// # Table format
+///
+/// All integers are little endian. The file starts with a 64 byte header:
+///
+/// - magic bytes `b"strpmap\0"`
+/// - format version, as a `u32` (currently 1), followed by a reserved `u32`
+/// - as `u64`s: number of entries, number of hash table slots (a power of two),
+///   and the offsets of the index, hash table and data sections, and the
+///   length of the data section
+///
+/// The index has an entry for every string, containing its offset into the
+/// data section, its length, and its 64-bit FNV-1a hash, all as `u64`s. The
+/// hash table is open addressed with linear probing, each slot containing
+/// the index of an entry as a `u32`, or `u32::MAX` if the slot is empty. The
+/// data section contains the bytes of every string, one after the other.
+#[derive(Clone)]
+pub struct MappedPool {
+	map: Arc<Mmap>,
+	count: usize,
+	table_len: usize,
+	index_offset: usize,
+	table_offset: usize,
+	data_offset: usize
+}
+
+#[cfg(test)]
+#[path = "../tests/mapped_pool.rs"]
+mod tests;
+
+impl MappedPool {
+	/// Maps the table file at `path`. The whole table is validated (including
+	/// that every string in it is valid UTF-8) before it's used.
+	///
+	/// # Safety
+	///
+	/// The file must not be modified (by this or any other process) while it
+	/// is mapped, ie. while this pool or any string from it is still alive.
+	pub unsafe fn open<T: AsRef<Path>>(path: T) -> Result<Self, SnapshotError> {
+		let pool = Self::open_unchecked(path)?;
+		pool.validate()?;
+		Ok(pool)
+	}
+
+	/// Maps the table file at `path`, only checking its header and that its
+	/// sections fit in the file, without going through every entry like
+	/// [`open`](Self::open) does. Opening a large table this way takes the same
+	/// (short) time no matter how many strings are in it.
+	///
+	/// # Safety
+	///
+	/// Same as [`open`](Self::open), and additionally the table must be valid,
+	/// for example because it was written by [`MappedPoolBuilder::write`] and
+	/// not modified since. A table with strings that aren't valid UTF-8 in it
+	/// is undefined behaviour; other problems (like wrong hashes, or a hash
+	/// table without empty slots) make lookups return wrong results, panic, or
+	/// never return.
+	pub unsafe fn open_unchecked<T: AsRef<Path>>(path: T) -> Result<Self, SnapshotError> {
+		let file = File::open(path)?;
+		let map = Mmap::map(&file)?;
+		Self::from_mmap(map)
+	}
+
+	/// Checks the header and layout of the table, which doesn't depend on the
+	/// number of entries in it
+	fn from_mmap(map: Mmap) -> Result<Self, SnapshotError> {
+		if map.len() < HEADER_LEN { return Err(SnapshotError::Truncated) }
+		if &map[..MAGIC.len()] != MAGIC { return Err(SnapshotError::BadMagic) }
+
+		let version = u32::from_le_bytes(map[8..12].try_into().unwrap());
+		if version != VERSION { return Err(SnapshotError::UnsupportedVersion(version)) }
+
+		let field = |i: usize| read_usize(&map, 16 + i * 8).ok_or(SnapshotError::Malformed);
+		let count = field(0)?;
+		let table_len = field(1)?;
+		let index_offset = field(2)?;
+		let table_offset = field(3)?;
+		let data_offset = field(4)?;
+		let data_len = field(5)?;
+
+		// sections have to be laid out one after the other, exactly
+		let expected = [
+			(index_offset, Some(HEADER_LEN)),
+			(table_offset, count.checked_mul(INDEX_ENTRY_LEN).and_then(|len| len.checked_add(index_offset))),
+			(data_offset, table_len.checked_mul(4).and_then(|len| len.checked_add(table_offset)))
+		];
+		if expected.iter().any(|(offset, expected)| Some(*offset) != *expected) { return Err(SnapshotError::Malformed) }
+		match data_offset.checked_add(data_len) {
+			Some(len) if len > map.len() => { return Err(SnapshotError::Truncated) }
+			Some(len) if len == map.len() => {}
+			_ => { return Err(SnapshotError::Malformed) }
+		}
+
+		// there must always be an empty slot, or probing would never end
+		if !table_len.is_power_of_two() || table_len <= count || count >= EMPTY as usize {
+			return Err(SnapshotError::Malformed)
+		}
+
+		Ok(Self { map: Arc::new(map), count, table_len, index_offset, table_offset, data_offset })
+	}
+
+	/// Checks every entry and hash table slot
+	fn validate(&self) -> Result<(), SnapshotError> {
+		for index in 0..self.count {
+			let (start, len, hash) = self.entry(index).ok_or(SnapshotError::Malformed)?;
+			let bytes = &self.map[start..start + len];
+
+			str::from_utf8(bytes).map_err(|_| SnapshotError::InvalidUtf8 { index })?;
+			if fnv(&SlicesWrap(&[bytes])) != hash { return Err(SnapshotError::Malformed) }
+		}
+
+		let mut filled = 0;
+		for slot in 0..self.table_len {
+			match self.slot(slot) {
+				EMPTY => {}
+				index if (index as usize) < self.count => { filled += 1 }
+				_ => { return Err(SnapshotError::Malformed) }
+			}
+		}
+		if filled > self.count { return Err(SnapshotError::Malformed) }
+
+		Ok(())
+	}
+
+	/// Number of strings in the table
+	pub fn len(&self) -> usize {
+		self.count
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.count == 0
+	}
+
+	/// Looks up a string in the table, returning `None` if it isn't in there
+	pub fn get(&self, s: &str) -> Option<crate::String<Self>> {
+		let raw = self.raw_get(SlicesWrap(&[s.as_bytes()]))?;
+		Some(unsafe { crate::String::from_raw_in(raw, self.clone()) })
+	}
+
+	/// Returns the absolute start, length and hash of an entry, checking that
+	/// the entry lies within the data section
+	fn entry(&self, index: usize) -> Option<(usize, usize, u64)> {
+		let offset = self.index_offset + index * INDEX_ENTRY_LEN;
+		let start = read_usize(&self.map, offset)?;
+		let len = read_usize(&self.map, offset + 8)?;
+		let hash = u64::from_le_bytes(self.map[offset + 16..offset + 24].try_into().unwrap());
+
+		let start = self.data_offset.checked_add(start)?;
+		if start.checked_add(len)? > self.map.len() { return None }
+		Some((start, len, hash))
+	}
+
+	fn slot(&self, slot: usize) -> u32 {
+		let offset = self.table_offset + slot * 4;
+		u32::from_le_bytes(self.map[offset..offset + 4].try_into().unwrap())
+	}
+
+	fn lookup(&self, slices: &SlicesWrap) -> Option<MappedRaw> {
+		let hash = fnv(slices);
+		let mask = self.table_len - 1;
+		let mut slot = hash as usize & mask;
+
+		loop {
+			let index = self.slot(slot);
+			if index == EMPTY { return None }
+
+			// validated when opening (or promised to be valid), so this can't fail
+			let (start, len, entry_hash) = self.entry(index as usize).unwrap();
+			if entry_hash == hash && slices.eq_bytes(&self.map[start..start + len]) {
+				return Some(MappedRaw { map: Arc::clone(&self.map), start, len, hash })
+			}
+
+			slot = (slot + 1) & mask;
+		}
+	}
+}
+
+impl Debug for MappedPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MappedPool")
+			.field("len", &self.count)
+			.finish()
+	}
+}
+
+/// Raw value of a string in a [`MappedPool`]'s table
+#[derive(Clone)]
+pub struct MappedRaw {
+	map: Arc<Mmap>,
+	start: usize,
+	len: usize,
+	hash: u64
+}
+
+impl MappedRaw {
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.map[self.start..self.start + self.len]
+	}
+}
+
+impl Debug for MappedRaw {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("MappedRaw")
+			.field("start", &self.start)
+			.field("len", &self.len)
+			.finish()
+	}
+}
+
+impl Pool for MappedPool {
+	type Raw = PooledOrOwned<MappedRaw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		match self.lookup(&slices) {
+			Some(raw) => { PooledOrOwned::Pooled(raw) }
+			None => { PooledOrOwned::Owned(slices.to_boxed_slice()) }
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { raw.as_bytes() }
+			PooledOrOwned::Owned(bytes) => { bytes }
+		}
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		self.lookup(&slices).map(PooledOrOwned::Pooled)
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { PooledOrOwned::Pooled(raw.clone()) }
+			PooledOrOwned::Owned(bytes) => { PooledOrOwned::Owned(bytes.clone()) }
+		}
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { Some(raw.hash) }
+			PooledOrOwned::Owned(_) => { None }
+		}
+	}
+}
+
+/// Builds a table file for [`MappedPool`]. Adding the same string more than
+/// once only stores it once.
+#[derive(Clone, Debug, Default)]
+pub struct MappedPoolBuilder {
+	strings: HashMap<Box<str>, usize>
+}
+
+impl MappedPoolBuilder {
+	pub fn new() -> Self {
+		Self { strings: HashMap::new() }
+	}
+
+	/// Adds a string to the table, returning `true` if it wasn't already in it
+	pub fn insert(&mut self, s: &str) -> bool {
+		if self.strings.contains_key(s) { return false }
+
+		let index = self.strings.len();
+		self.strings.insert(s.into(), index);
+		true
+	}
+
+	/// Number of (distinct) strings added so far
+	pub fn len(&self) -> usize {
+		self.strings.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.strings.is_empty()
+	}
+
+	/// Writes the table to `writer`, with strings in the order they were first
+	/// added
+	pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+		let mut strings = vec![""; self.strings.len()];
+		self.strings.iter().for_each(|(s, index)| strings[*index] = s);
+
+		let count = strings.len();
+		if count >= EMPTY as usize {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "too many strings for a mapped pool table"))
+		}
+		let table_len = (count * 2).next_power_of_two();
+
+		let mut index = Vec::with_capacity(count * INDEX_ENTRY_LEN);
+		let mut table = vec![EMPTY; table_len];
+		let mut data_len = 0;
+
+		for (i, s) in strings.iter().enumerate() {
+			let hash = fnv(&SlicesWrap(&[s.as_bytes()]));
+			index.extend_from_slice(&(data_len as u64).to_le_bytes());
+			index.extend_from_slice(&(s.len() as u64).to_le_bytes());
+			index.extend_from_slice(&hash.to_le_bytes());
+			data_len += s.len();
+
+			let mut slot = hash as usize & (table_len - 1);
+			while table[slot] != EMPTY { slot = (slot + 1) & (table_len - 1) }
+			table[slot] = i as u32;
+		}
+
+		let index_offset = HEADER_LEN;
+		let table_offset = index_offset + index.len();
+		let data_offset = table_offset + table_len * 4;
+
+		writer.write_all(MAGIC)?;
+		writer.write_all(&VERSION.to_le_bytes())?;
+		writer.write_all(&0u32.to_le_bytes())?;
+		for field in [count, table_len, index_offset, table_offset, data_offset, data_len] {
+			writer.write_all(&(field as u64).to_le_bytes())?;
+		}
+
+		writer.write_all(&index)?;
+		table.iter().try_for_each(|slot| writer.write_all(&slot.to_le_bytes()))?;
+		strings.iter().try_for_each(|s| writer.write_all(s.as_bytes()))?;
+		writer.flush()
+	}
+}
+
+impl<'s> Extend<&'s str> for MappedPoolBuilder {
+	fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+		iter.into_iter().for_each(|s| { self.insert(s); })
+	}
+}
+
+fn read_usize(bytes: &[u8], offset: usize) -> Option<usize> {
+	let bytes = bytes.get(offset..offset.checked_add(8)?)?;
+	u64::from_le_bytes(bytes.try_into().unwrap()).try_into().ok()
+}
@@ -0,0 +1,66 @@
+use super::{ Canonicalizer, CanonicalizingPool, Pool };
+use ::std::borrow::Cow;
+use ::unicode_normalization::{ is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization };
+
+/// A pool that normalizes strings to a Unicode normalization form before
+/// interning them, so that strings that are canonically equivalent (or
+/// compatibility equivalent, for NFKC), such as a precomposed `"é"` and an
+/// `"e"` followed by a combining acute accent, share the same entry and
+/// compare equal.
+///
+/// This is a [`CanonicalizingPool`] using a [`UnicodeNormalizer`], so it can
+/// also keep the original spelling around, and be combined with other
+/// canonicalizers.
+pub type NormalizingPool<P> = CanonicalizingPool<P, UnicodeNormalizer>;
+
+#[cfg(test)]
+#[path = "../tests/normalizing_pool.rs"]
+mod tests;
+
+impl<P: Pool> NormalizingPool<P> {
+	pub fn nfc(pool: P) -> Self {
+		Self::new(pool, UnicodeNormalizer::Nfc)
+	}
+
+	pub fn nfkc(pool: P) -> Self {
+		Self::new(pool, UnicodeNormalizer::Nfkc)
+	}
+}
+
+/// [`Canonicalizer`] that normalizes strings to a Unicode normalization form.
+///
+/// Strings that are ASCII, or are quickly determined to already be
+/// normalized, are passed through without copying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnicodeNormalizer {
+	/// Normalization Form C (canonical composition)
+	Nfc,
+	/// Normalization Form KC (compatibility composition)
+	Nfkc
+}
+
+impl Canonicalizer for UnicodeNormalizer {
+	fn canonicalize<'s>(&self, s: &'s str) -> Cow<'s, str> {
+		// ASCII is unaffected by both normalization forms
+		if s.is_ascii() { return Cow::Borrowed(s) }
+
+		let quick = match self {
+			Self::Nfc => { is_nfc_quick(s.chars()) }
+			Self::Nfkc => { is_nfkc_quick(s.chars()) }
+		};
+		if quick == IsNormalized::Yes { return Cow::Borrowed(s) }
+
+		let normalized = match self {
+			Self::Nfc => { s.nfc().collect::<::std::string::String>() }
+			Self::Nfkc => { s.nfkc().collect::<::std::string::String>() }
+		};
+
+		// quick check can say "maybe", in which case it may have been
+		// normalized already after all
+		if normalized == s {
+			Cow::Borrowed(s)
+		} else {
+			Cow::Owned(normalized)
+		}
+	}
+}
@@ -0,0 +1,242 @@
+use super::{ Pool, PooledOrOwned, SlicesWrap };
+use ::hashbrown::hash_map::DefaultHashBuilder;
+use ::parking_lot::Mutex;
+use ::std::fmt::{ self, Debug };
+use ::std::hash::BuildHasher;
+use ::std::sync::Arc;
+
+/// Wraps another pool, only interning strings into it that an [`InternPolicy`]
+/// decides are worth interning. Strings that the policy rejects get their own
+/// owned allocation that isn't shared with anything, but otherwise work as a
+/// [`String`](crate::String) exactly like any other.
+///
+/// The policy is asked first. A rejected string is still looked up in the
+/// wrapped pool (without inserting it), so a string that was interned before,
+/// when the policy still accepted it, keeps being shared instead of copied.
+#[derive(Clone, Debug)]
+pub struct SelectivePool<P, I> {
+	pool: P,
+	policy: I
+}
+
+#[cfg(test)]
+#[path = "../tests/selective_pool.rs"]
+mod tests;
+
+impl<P: Pool, I: InternPolicy> SelectivePool<P, I> {
+	pub fn new(pool: P, policy: I) -> Self {
+		Self { pool, policy }
+	}
+
+	/// Returns a reference to the wrapped pool
+	pub fn inner(&self) -> &P {
+		&self.pool
+	}
+
+	pub fn policy(&self) -> &I {
+		&self.policy
+	}
+}
+
+/// Decides which strings a [`SelectivePool`] interns
+pub trait InternPolicy: Clone {
+	/// Returns `true` if the string should be interned into the wrapped pool,
+	/// or `false` if it should get its own allocation instead.
+	fn should_intern(&self, slices: &SlicesWrap) -> bool;
+}
+
+/// Interns strings that are at most this many bytes long
+#[derive(Clone, Copy, Debug)]
+pub struct MaxLen(pub usize);
+
+impl InternPolicy for MaxLen {
+	fn should_intern(&self, slices: &SlicesWrap) -> bool {
+		slices.len() <= self.0
+	}
+}
+
+/// Interns strings that a closure returns `true` for
+pub struct Predicate<F> {
+	f: Arc<F>
+}
+
+impl<F> Predicate<F>
+where
+	F: Fn(&SlicesWrap) -> bool
+{
+	pub fn new(f: F) -> Self {
+		Self { f: Arc::new(f) }
+	}
+}
+
+impl<F> Clone for Predicate<F> {
+	fn clone(&self) -> Self {
+		Self { f: Arc::clone(&self.f) }
+	}
+}
+
+impl<F> Debug for Predicate<F> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Predicate").finish_non_exhaustive()
+	}
+}
+
+impl<F> InternPolicy for Predicate<F>
+where
+	F: Fn(&SlicesWrap) -> bool
+{
+	fn should_intern(&self, slices: &SlicesWrap) -> bool {
+		(self.f)(slices)
+	}
+}
+
+/// Interns strings once they have been seen at least `threshold` times.
+///
+/// How often a string has been seen is tracked using a count-min sketch, a
+/// small fixed-size table of counters. It never underestimates how often a
+/// string has been seen, but can overestimate it when strings collide, so
+/// some strings may get interned a bit earlier than the threshold.
+///
+/// Every counter is halved after every `window` strings seen (like TinyLFU
+/// does), so counts from long ago fade out, and a string has to be seen about
+/// `threshold` times within a window to get interned. Strings that keep
+/// turning up, but only rarely, never get interned.
+///
+/// Clones share the same counters.
+#[derive(Clone)]
+pub struct FrequencyThreshold {
+	threshold: u32,
+	sketch: Arc<Mutex<Sketch>>
+}
+
+struct Sketch {
+	hash_builder: DefaultHashBuilder,
+	width: usize,
+	/// `DEPTH` rows of `width` counters each
+	counters: Box<[u32]>,
+	window: usize,
+	/// strings seen since the counters were last halved
+	seen: usize
+}
+
+impl FrequencyThreshold {
+	/// Number of rows in the sketch
+	const DEPTH: usize = 4;
+	/// Default number of counters per row
+	pub const DEFAULT_WIDTH: usize = 1024;
+	/// Default window, as a multiple of the width
+	const WINDOW_PER_WIDTH: usize = 10;
+
+	pub fn new(threshold: u32) -> Self {
+		Self::with_width(threshold, Self::DEFAULT_WIDTH)
+	}
+
+	/// Creates a threshold policy with `width` counters per row in its sketch.
+	/// Wider sketches overestimate less often, but use more memory. The window
+	/// is 10 times the width.
+	pub fn with_width(threshold: u32, width: usize) -> Self {
+		let width = width.max(1);
+		let sketch = Sketch {
+			hash_builder: DefaultHashBuilder::default(),
+			width,
+			counters: vec![0; width * Self::DEPTH].into_boxed_slice(),
+			window: width.saturating_mul(Self::WINDOW_PER_WIDTH),
+			seen: 0
+		};
+		Self { threshold, sketch: Arc::new(Mutex::new(sketch)) }
+	}
+
+	/// Sets the number of strings seen after which every counter is halved
+	pub fn with_window(self, window: usize) -> Self {
+		self.sketch.lock().window = window.max(1);
+		self
+	}
+
+	pub fn threshold(&self) -> u32 {
+		self.threshold
+	}
+
+	pub fn window(&self) -> usize {
+		self.sketch.lock().window
+	}
+}
+
+impl Debug for FrequencyThreshold {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sketch = self.sketch.lock();
+		f.debug_struct("FrequencyThreshold")
+			.field("threshold", &self.threshold)
+			.field("width", &sketch.width)
+			.field("window", &sketch.window)
+			.finish()
+	}
+}
+
+impl InternPolicy for FrequencyThreshold {
+	fn should_intern(&self, slices: &SlicesWrap) -> bool {
+		let mut sketch = self.sketch.lock();
+		let Sketch { hash_builder, width, counters, window, seen } = &mut *sketch;
+
+		// splitting one hash into two, and combining those for every row
+		// (Kirsch-Mitzenmacher), so we only need to hash the string once. `h2`
+		// is odd, so rows never all use the same counter (for power of two
+		// widths, that is)
+		let hash = hash_builder.hash_one(slices);
+		let (h1, h2) = (hash as u32 as usize, (hash >> 32) as usize | 1);
+
+		let count = counters.chunks_exact_mut(*width)
+			.enumerate()
+			.map(|(i, row)| {
+				let counter = &mut row[h1.wrapping_add(i.wrapping_mul(h2)) % *width];
+				*counter = counter.saturating_add(1);
+				*counter
+			})
+			.min()
+			.unwrap_or(u32::MAX);
+
+		*seen += 1;
+		if *seen >= *window {
+			counters.iter_mut().for_each(|counter| *counter /= 2);
+			*seen = 0;
+		}
+
+		count >= self.threshold
+	}
+}
+
+impl<P: Pool, I: InternPolicy> Pool for SelectivePool<P, I> {
+	type Raw = PooledOrOwned<P::Raw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		if self.policy.should_intern(&slices) {
+			return PooledOrOwned::Pooled(self.pool.raw_from_slices(slices))
+		}
+
+		// already interned (for example, it passed the policy before), so
+		// sharing that is better than making another copy
+		match self.pool.raw_get(SlicesWrap(slices.0)) {
+			Some(raw) => { PooledOrOwned::Pooled(raw) }
+			None => { PooledOrOwned::Owned(slices.to_boxed_slice()) }
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw.as_slice(&self.pool)
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		self.pool.raw_get(slices).map(PooledOrOwned::Pooled)
+	}
+
+	fn raw_into_vec(&self, raw: Self::Raw) -> Vec<u8> {
+		raw.into_vec(&self.pool)
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		raw.clone_in(&self.pool)
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		raw.precomputed_hash(&self.pool)
+	}
+}
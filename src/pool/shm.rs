@@ -0,0 +1,445 @@
+use super::{ Pool, PooledOrOwned, SlicesWrap };
+use super::snapshot::fnv;
+use ::std::cell::UnsafeCell;
+use ::std::ffi::CString;
+use ::std::fmt::{ self, Debug };
+use ::std::io;
+use ::std::marker::PhantomData;
+use ::std::mem::{ size_of, MaybeUninit };
+use ::std::ptr;
+use ::std::slice;
+use ::std::sync::Arc;
+use ::std::sync::atomic::{ AtomicU64, Ordering };
+
+const MAGIC: u64 = u64::from_le_bytes(*b"strpshm\0");
+const VERSION: u32 = 1;
+const ENTRY_HEADER_LEN: usize = 16;
+
+/// A pool stored in a named POSIX shared memory segment, so that every process
+/// attached to the same segment shares the same strings.
+///
+/// The segment contains a hash table and an arena the strings are appended
+/// to, both with a fixed capacity chosen when the segment is created. Once it
+/// is full, new strings get their own (heap) allocation that isn't shared,
+/// like in [`BoundedPool`](super::BoundedPool).
+///
+/// Raw values are offsets into the segment, so the same string has the same
+/// offset (see `ShmRaw::offset`) in every process, even though the segment
+/// is mapped at different addresses. Strings are never removed from the
+/// segment.
+///
+/// Inserting is coordinated between processes using a process-shared mutex
+/// stored in the segment. Looking strings up doesn't need to lock anything,
+/// since entries never change once they're added. All processes attached to a
+/// segment are trusted not to corrupt it.
+///
+/// On Linux the mutex is robust, so if a process dies while holding it, the
+/// next process to lock it takes over. Other platforms don't have robust
+/// mutexes, so a process crashing while inserting leaves the segment locked
+/// for good, and every process that tries to insert into it afterwards blocks
+/// forever (looking strings up keeps working).
+#[derive(Clone)]
+pub struct ShmPool {
+	segment: Arc<Segment>
+}
+
+#[cfg(test)]
+#[path = "../tests/shm_pool.rs"]
+mod tests;
+
+/// Start of the shared memory segment. The hash table (an `AtomicU64` per
+/// slot, containing the offset of an entry or 0 if empty) comes right after
+/// it, followed by the arena.
+#[repr(C)]
+struct Header {
+	/// Set last when creating the segment, so processes attaching to it don't
+	/// see it half initialised
+	magic: AtomicU64,
+	version: u32,
+	_reserved: u32,
+	table_len: u64,
+	arena_len: u64,
+	/// Bytes of the arena used
+	used: AtomicU64,
+	/// Number of entries
+	count: AtomicU64,
+	lock: UnsafeCell<libc::pthread_mutex_t>
+}
+
+/// A mapping of a shared memory segment into this process
+struct Segment {
+	ptr: *mut u8,
+	len: usize
+}
+
+// SAFETY: all shared state in the segment is either atomics, only written to
+// while holding the process-shared lock, or immutable once published
+unsafe impl Send for Segment {}
+unsafe impl Sync for Segment {}
+
+impl ShmPool {
+	/// Creates a new shared memory segment called `name` (which, for
+	/// portability, should start with a `/` and contain no other slashes), with
+	/// room for `max_entries` strings and `max_bytes` bytes of string data
+	/// (plus 16 bytes per string). Fails if a segment with that name already
+	/// exists.
+	pub fn create(name: &str, max_entries: usize, max_bytes: usize) -> io::Result<Self> {
+		let table_len = max_entries.max(1).checked_mul(2)
+			.map(usize::next_power_of_two)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "max_entries too large"))?;
+		let arena_len = max_entries.checked_mul(ENTRY_HEADER_LEN + 7)
+			.and_then(|len| len.checked_add(max_bytes))
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "max_bytes too large"))?;
+		let len = Segment::arena_offset(table_len)
+			.and_then(|offset| offset.checked_add(arena_len))
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "max_bytes too large"))?;
+
+		let name = shm_name(name)?;
+		let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR | libc::O_CREAT | libc::O_EXCL, 0o600) };
+		if fd < 0 { return Err(io::Error::last_os_error()) }
+
+		let segment = unsafe {
+			let result = match libc::ftruncate(fd, len as libc::off_t) {
+				0 => { Segment::map(fd, len) }
+				_ => { Err(io::Error::last_os_error()) }
+			};
+			libc::close(fd);
+
+			match result {
+				Ok(segment) => { segment }
+				Err(e) => {
+					libc::shm_unlink(name.as_ptr());
+					return Err(e)
+				}
+			}
+		};
+
+		// freshly truncated, so everything is already zeroed
+		unsafe {
+			let header = segment.ptr as *mut Header;
+			ptr::addr_of_mut!((*header).version).write(VERSION);
+			ptr::addr_of_mut!((*header).table_len).write(table_len as u64);
+			ptr::addr_of_mut!((*header).arena_len).write(arena_len as u64);
+			if let Err(e) = init_lock((*header).lock.get()) {
+				libc::shm_unlink(name.as_ptr());
+				return Err(e)
+			}
+		}
+		segment.header().magic.store(MAGIC, Ordering::Release);
+
+		Ok(Self { segment: Arc::new(segment) })
+	}
+
+	/// Attaches to an existing segment created by [`create`](Self::create)
+	pub fn open(name: &str) -> io::Result<Self> {
+		let name = shm_name(name)?;
+		let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR, 0) };
+		if fd < 0 { return Err(io::Error::last_os_error()) }
+
+		let segment = unsafe {
+			let result = Segment::open(fd);
+			libc::close(fd);
+			result?
+		};
+
+		Ok(Self { segment: Arc::new(segment) })
+	}
+
+	/// Removes the segment called `name`. Processes that are attached to it
+	/// stay attached, but it can't be opened anymore.
+	pub fn unlink(name: &str) -> io::Result<()> {
+		let name = shm_name(name)?;
+		match unsafe { libc::shm_unlink(name.as_ptr()) } {
+			0 => { Ok(()) }
+			_ => { Err(io::Error::last_os_error()) }
+		}
+	}
+
+	/// Number of strings in the segment
+	pub fn len(&self) -> usize {
+		self.segment.header().count.load(Ordering::Acquire) as usize
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Bytes of the segment's arena in use
+	pub fn bytes(&self) -> usize {
+		self.segment.header().used.load(Ordering::Acquire) as usize
+	}
+
+	/// Looks up a string in the pool, returning `None` if it isn't in there
+	pub fn get(&self, s: &str) -> Option<crate::String<Self>> {
+		let raw = self.raw_get(SlicesWrap(&[s.as_bytes()]))?;
+		Some(unsafe { crate::String::from_raw_in(raw, self.clone()) })
+	}
+
+	/// Probes the table for `slices`, returning the offset of its entry if
+	/// found, or the empty slot it would go in otherwise
+	fn probe(&self, hash: u64, slices: &SlicesWrap) -> Result<u64, usize> {
+		let segment = &*self.segment;
+		let mask = segment.header().table_len as usize - 1;
+		let mut slot = hash as usize & mask;
+
+		loop {
+			let offset = segment.slot(slot).load(Ordering::Acquire);
+			if offset == 0 { return Err(slot) }
+
+			let (entry_hash, bytes) = unsafe { segment.entry(offset) };
+			if entry_hash == hash && slices.eq_bytes(bytes) { return Ok(offset) }
+
+			slot = (slot + 1) & mask;
+		}
+	}
+
+	fn insert(&self, hash: u64, slices: &SlicesWrap) -> Option<u64> {
+		let segment = &*self.segment;
+		let header = segment.header();
+		let _guard = segment.lock();
+
+		// someone could have inserted it since we last looked
+		let slot = match self.probe(hash, slices) {
+			Ok(offset) => { return Some(offset) }
+			Err(slot) => { slot }
+		};
+
+		// keep the table at most half full, so probing stays short
+		let count = header.count.load(Ordering::Relaxed);
+		if (count + 1) * 2 > header.table_len { return None }
+
+		let used = header.used.load(Ordering::Relaxed) as usize;
+		let len = slices.len();
+		let size = (ENTRY_HEADER_LEN + len + 7) & !7;
+		if used + size > header.arena_len as usize { return None }
+
+		// checked when the segment was created or opened
+		let offset = Segment::arena_offset(header.table_len as usize).unwrap() + used;
+		unsafe {
+			let entry = segment.ptr.add(offset);
+			(entry as *mut u64).write(hash);
+			(entry.add(8) as *mut u64).write(len as u64);
+
+			let mut dest = entry.add(ENTRY_HEADER_LEN);
+			for slice in slices.0 {
+				ptr::copy_nonoverlapping(slice.as_ptr(), dest, slice.len());
+				dest = dest.add(slice.len());
+			}
+		}
+
+		header.used.store((used + size) as u64, Ordering::Relaxed);
+		header.count.store(count + 1, Ordering::Release);
+		// publishing the entry last, so anyone who sees it sees all of it
+		segment.slot(slot).store(offset as u64, Ordering::Release);
+
+		Some(offset as u64)
+	}
+
+	fn raw(&self, offset: u64) -> ShmRaw {
+		ShmRaw { segment: Arc::clone(&self.segment), offset }
+	}
+}
+
+impl Debug for ShmPool {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ShmPool")
+			.field("len", &self.len())
+			.field("bytes", &self.bytes())
+			.finish()
+	}
+}
+
+impl Segment {
+	unsafe fn map(fd: libc::c_int, len: usize) -> io::Result<Self> {
+		let ptr = libc::mmap(ptr::null_mut(), len, libc::PROT_READ | libc::PROT_WRITE, libc::MAP_SHARED, fd, 0);
+		if ptr == libc::MAP_FAILED { return Err(io::Error::last_os_error()) }
+		Ok(Self { ptr: ptr as *mut u8, len })
+	}
+
+	/// Reads and checks the header of the segment in `fd`, only mapping it if
+	/// the table and arena the header describes fill the segment exactly
+	unsafe fn open(fd: libc::c_int) -> io::Result<Self> {
+		let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+
+		let mut stat = MaybeUninit::<libc::stat>::uninit();
+		if libc::fstat(fd, stat.as_mut_ptr()) != 0 { return Err(io::Error::last_os_error()) }
+		let len = usize::try_from(stat.assume_init().st_size)
+			.map_err(|_| invalid("segment size doesn't match its header"))?;
+		if len < size_of::<Header>() { return Err(invalid("segment is too small")) }
+
+		// only plain integers in there, so any bytes are a valid header
+		let mut header = MaybeUninit::<Header>::uninit();
+		let read = libc::pread(fd, header.as_mut_ptr() as *mut libc::c_void, size_of::<Header>(), 0);
+		if read < 0 { return Err(io::Error::last_os_error()) }
+		if read as usize != size_of::<Header>() { return Err(invalid("segment is too small")) }
+		let header = header.assume_init();
+
+		if header.magic.load(Ordering::Relaxed) != MAGIC { return Err(invalid("not a string pool segment")) }
+		if header.version != VERSION { return Err(invalid("unsupported segment version")) }
+
+		let expected_len = usize::try_from(header.table_len).ok()
+			.filter(|table_len| table_len.is_power_of_two())
+			.and_then(Segment::arena_offset)
+			.zip(usize::try_from(header.arena_len).ok())
+			.and_then(|(offset, arena_len)| offset.checked_add(arena_len));
+		if expected_len != Some(len) { return Err(invalid("segment size doesn't match its header")) }
+
+		let segment = Segment::map(fd, len)?;
+		// synchronises with the store in `create`, for everything else in the
+		// header (like the lock)
+		segment.header().magic.load(Ordering::Acquire);
+		Ok(segment)
+	}
+
+	/// `None` if it doesn't fit in a `usize`
+	fn arena_offset(table_len: usize) -> Option<usize> {
+		table_len.checked_mul(size_of::<u64>())?
+			.checked_add(size_of::<Header>().next_multiple_of(8))
+	}
+
+	fn header(&self) -> &Header {
+		unsafe { &*(self.ptr as *const Header) }
+	}
+
+	fn slot(&self, slot: usize) -> &AtomicU64 {
+		unsafe { &*(self.ptr.add(size_of::<Header>().next_multiple_of(8)) as *const AtomicU64).add(slot) }
+	}
+
+	/// # Safety
+	///
+	/// `offset` must be the offset of a published entry
+	unsafe fn entry(&self, offset: u64) -> (u64, &[u8]) {
+		let entry = self.ptr.add(offset as usize);
+		let hash = (entry as *const u64).read();
+		let len = (entry.add(8) as *const u64).read() as usize;
+		(hash, slice::from_raw_parts(entry.add(ENTRY_HEADER_LEN), len))
+	}
+
+	fn lock(&self) -> LockGuard<'_> {
+		let lock = self.header().lock.get();
+		match unsafe { libc::pthread_mutex_lock(lock) } {
+			0 => {}
+			// a process died while holding the lock. Entries are published
+			// last, so at worst it leaked some arena space
+			#[cfg(target_os = "linux")]
+			libc::EOWNERDEAD => { unsafe { libc::pthread_mutex_consistent(lock); } }
+			e => { panic!("failed to lock shared memory pool: {}", io::Error::from_raw_os_error(e)) }
+		}
+		LockGuard { lock, _segment: PhantomData }
+	}
+}
+
+impl Drop for Segment {
+	fn drop(&mut self) {
+		unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len); }
+	}
+}
+
+struct LockGuard<'s> {
+	lock: *mut libc::pthread_mutex_t,
+	_segment: PhantomData<&'s Segment>
+}
+
+impl<'s> Drop for LockGuard<'s> {
+	fn drop(&mut self) {
+		unsafe { libc::pthread_mutex_unlock(self.lock); }
+	}
+}
+
+unsafe fn init_lock(lock: *mut libc::pthread_mutex_t) -> io::Result<()> {
+	let check = |result: libc::c_int| match result {
+		0 => { Ok(()) }
+		e => { Err(io::Error::from_raw_os_error(e)) }
+	};
+
+	let mut attr = MaybeUninit::<libc::pthread_mutexattr_t>::uninit();
+	check(libc::pthread_mutexattr_init(attr.as_mut_ptr()))?;
+	let result = (|| {
+		check(libc::pthread_mutexattr_setpshared(attr.as_mut_ptr(), libc::PTHREAD_PROCESS_SHARED))?;
+		#[cfg(target_os = "linux")]
+		check(libc::pthread_mutexattr_setrobust(attr.as_mut_ptr(), libc::PTHREAD_MUTEX_ROBUST))?;
+		check(libc::pthread_mutex_init(lock, attr.as_ptr()))
+	})();
+	libc::pthread_mutexattr_destroy(attr.as_mut_ptr());
+	result
+}
+
+fn shm_name(name: &str) -> io::Result<CString> {
+	CString::new(name)
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "segment name contains a nul byte"))
+}
+
+/// Raw value of a string in a [`ShmPool`]'s segment
+#[derive(Clone)]
+pub struct ShmRaw {
+	segment: Arc<Segment>,
+	offset: u64
+}
+
+impl ShmRaw {
+	/// Offset of the string's entry in the segment. This is the same in every
+	/// process attached to the segment, so comparing offsets is the same as
+	/// comparing pointers.
+	pub fn offset(&self) -> u64 {
+		self.offset
+	}
+
+	pub fn as_bytes(&self) -> &[u8] {
+		unsafe { self.segment.entry(self.offset).1 }
+	}
+
+	fn hash(&self) -> u64 {
+		unsafe { self.segment.entry(self.offset).0 }
+	}
+}
+
+impl Debug for ShmRaw {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ShmRaw")
+			.field("offset", &self.offset)
+			.finish()
+	}
+}
+
+impl Pool for ShmPool {
+	type Raw = PooledOrOwned<ShmRaw>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let hash = fnv(&slices);
+		let offset = match self.probe(hash, &slices) {
+			Ok(offset) => { Some(offset) }
+			Err(_) => { self.insert(hash, &slices) }
+		};
+
+		match offset {
+			Some(offset) => { PooledOrOwned::Pooled(self.raw(offset)) }
+			None => { PooledOrOwned::Owned(slices.to_boxed_slice()) }
+		}
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { raw.as_bytes() }
+			PooledOrOwned::Owned(bytes) => { bytes }
+		}
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		let offset = self.probe(fnv(&slices), &slices).ok()?;
+		Some(PooledOrOwned::Pooled(self.raw(offset)))
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { PooledOrOwned::Pooled(raw.clone()) }
+			PooledOrOwned::Owned(bytes) => { PooledOrOwned::Owned(bytes.clone()) }
+		}
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		match raw {
+			PooledOrOwned::Pooled(raw) => { Some(raw.hash()) }
+			PooledOrOwned::Owned(_) => { None }
+		}
+	}
+}
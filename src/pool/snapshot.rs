@@ -0,0 +1,244 @@
+//! Binary snapshot format for saving the contents of a pool, and loading them
+//! back into a pool later (for example, after restarting).
+//!
+//! The format is documented on [`GlobalPool::save_snapshot`](super::GlobalPool::save_snapshot).
+//!
+//! Hashes aren't stored, since the pool's hasher is randomly seeded every time
+//! the process starts.
+
+use super::SlicesWrap;
+use ::std::fmt;
+use ::std::error::Error;
+use ::std::io::{ self, Read, Write };
+use ::std::str;
+
+const MAGIC: &[u8; 8] = b"strpool\0";
+const VERSION: u32 = 1;
+
+#[cfg(test)]
+#[path = "../tests/snapshot.rs"]
+mod tests;
+
+/// Error returned when loading a snapshot or decoding a [string table](crate::table)
+/// fails (or, with the `mmap` feature, when opening a `MappedPool` table fails)
+#[derive(Debug)]
+pub enum SnapshotError {
+	/// Reading the snapshot failed
+	Io(io::Error),
+	/// The data doesn't start with the snapshot magic bytes, so probably isn't
+	/// a snapshot at all
+	BadMagic,
+	/// The snapshot was written using a format version this version of the
+	/// crate doesn't understand
+	UnsupportedVersion(u32),
+	/// The snapshot ended before all of it could be read
+	Truncated,
+	/// The checksum stored in the snapshot doesn't match its contents
+	ChecksumMismatch,
+	/// The checksum matched, but the contents don't make sense (for example
+	/// there are leftover bytes after the last entry)
+	Malformed,
+	/// The entry at `index` isn't valid UTF-8
+	InvalidUtf8 { index: usize }
+}
+
+impl fmt::Display for SnapshotError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Io(e) => { write!(f, "failed to read snapshot: {e}") }
+			Self::BadMagic => { f.write_str("not a string pool snapshot") }
+			Self::UnsupportedVersion(v) => { write!(f, "unsupported snapshot version {v}") }
+			Self::Truncated => { f.write_str("snapshot is truncated") }
+			Self::ChecksumMismatch => { f.write_str("snapshot checksum mismatch") }
+			Self::Malformed => { f.write_str("snapshot is malformed") }
+			Self::InvalidUtf8 { index } => { write!(f, "snapshot entry {index} is not valid UTF-8") }
+		}
+	}
+}
+
+impl Error for SnapshotError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			Self::Io(e) => { Some(e) }
+			_ => { None }
+		}
+	}
+}
+
+impl From<io::Error> for SnapshotError {
+	fn from(e: io::Error) -> Self {
+		Self::Io(e)
+	}
+}
+
+/// Writes a snapshot containing `entries` to `writer`
+pub(crate) fn write<'e, W, I>(mut writer: W, entries: I) -> io::Result<()>
+where
+	W: Write,
+	I: ExactSizeIterator<Item = &'e [u8]>
+{
+	let mut writer = ChecksumWriter { writer: &mut writer, checksum: Fnv::new() };
+
+	writer.write_all(MAGIC)?;
+	writer.write_all(&VERSION.to_le_bytes())?;
+	write_varint(&mut writer, entries.len() as u64)?;
+
+	for entry in entries {
+		write_varint(&mut writer, entry.len() as u64)?;
+		writer.write_all(entry)?;
+	}
+
+	let checksum = writer.checksum.finish();
+	writer.writer.write_all(&checksum.to_le_bytes())?;
+	writer.writer.flush()
+}
+
+/// Reads and fully validates a snapshot from `reader`, then calls `f` with
+/// every entry in it. Returns the number of entries.
+///
+/// Nothing is passed to `f` unless the whole snapshot is valid, so a corrupt
+/// snapshot never leaves a pool partially loaded.
+pub(crate) fn read<R, F>(mut reader: R, mut f: F) -> Result<usize, SnapshotError>
+where
+	R: Read,
+	F: FnMut(&str)
+{
+	let mut buf = Vec::new();
+	reader.read_to_end(&mut buf)?;
+
+	if buf.len() < MAGIC.len() { return Err(SnapshotError::Truncated) }
+	if &buf[..MAGIC.len()] != MAGIC { return Err(SnapshotError::BadMagic) }
+
+	let header_len = MAGIC.len() + 4;
+	if buf.len() < header_len { return Err(SnapshotError::Truncated) }
+	let version = u32::from_le_bytes(buf[MAGIC.len()..header_len].try_into().unwrap());
+	if version != VERSION { return Err(SnapshotError::UnsupportedVersion(version)) }
+
+	if buf.len() < header_len + 8 { return Err(SnapshotError::Truncated) }
+	let (contents, checksum) = buf.split_at(buf.len() - 8);
+	let checksum = u64::from_le_bytes(checksum.try_into().unwrap());
+
+	let mut fnv = Fnv::new();
+	fnv.write(contents);
+	if fnv.finish() != checksum { return Err(SnapshotError::ChecksumMismatch) }
+
+	// the checksum was verified, so anything off means the snapshot is
+	// malformed, not truncated
+	let varint = |bytes: &mut &[u8]| match read_varint(bytes) {
+		Err(SnapshotError::Truncated) => { Err(SnapshotError::Malformed) }
+		result => { result }
+	};
+
+	let mut rest = &contents[header_len..];
+	let count = varint(&mut rest)?;
+
+	// validate everything first, before giving anything to `f`. Not trusting
+	// `count` for the capacity, every entry takes at least one byte
+	let mut entries = Vec::with_capacity((count as usize).min(rest.len()));
+	for index in 0..count as usize {
+		let len = varint(&mut rest)?;
+		if (rest.len() as u64) < len { return Err(SnapshotError::Malformed) }
+
+		let (entry, remaining) = rest.split_at(len as usize);
+		let entry = str::from_utf8(entry)
+			.map_err(|_| SnapshotError::InvalidUtf8 { index })?;
+
+		entries.push(entry);
+		rest = remaining;
+	}
+	if !rest.is_empty() { return Err(SnapshotError::Malformed) }
+
+	entries.iter().for_each(|entry| f(entry));
+	Ok(entries.len())
+}
+
+pub(crate) fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+	let mut buf = [0u8; 10];
+	let mut len = 0;
+
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+
+		if value == 0 {
+			buf[len] = byte;
+			len += 1;
+			break
+		}
+
+		buf[len] = byte | 0x80;
+		len += 1;
+	}
+
+	writer.write_all(&buf[..len])
+}
+
+/// Reads a varint written by [`write_varint`] from `reader`
+pub(crate) fn read_varint<R: Read>(reader: &mut R) -> Result<u64, SnapshotError> {
+	let mut value = 0u64;
+
+	for shift in (0..64).step_by(7) {
+		let mut byte = [0u8];
+		match reader.read_exact(&mut byte) {
+			Ok(()) => {}
+			Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => { return Err(SnapshotError::Truncated) }
+			Err(e) => { return Err(e.into()) }
+		}
+
+		// only the lowest bit of the 10th byte still fits in a u64
+		if shift == 63 && byte[0] > 1 { return Err(SnapshotError::Malformed) }
+
+		value |= ((byte[0] & 0x7f) as u64) << shift;
+		if byte[0] & 0x80 == 0 { return Ok(value) }
+	}
+
+	Err(SnapshotError::Malformed)
+}
+
+/// 64-bit FNV-1a of the joined `slices`, for tables that are shared with
+/// other processes, where the hash has to be the same in every process and on
+/// every platform
+pub(crate) fn fnv(slices: &SlicesWrap) -> u64 {
+	let mut fnv = Fnv::new();
+	slices.0.iter().for_each(|slice| fnv.write(slice));
+	fnv.finish()
+}
+
+/// 64-bit FNV-1a, chosen because it's simple and stable across versions
+/// and platforms, unlike the pool's own (randomly seeded) hasher
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+	pub(crate) fn new() -> Self {
+		Self(0xcbf29ce484222325)
+	}
+
+	pub(crate) fn write(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			self.0 ^= byte as u64;
+			self.0 = self.0.wrapping_mul(0x100000001b3);
+		}
+	}
+
+	pub(crate) fn finish(&self) -> u64 {
+		self.0
+	}
+}
+
+/// Passes writes through to `writer`, updating the checksum along the way
+struct ChecksumWriter<'w, W> {
+	writer: &'w mut W,
+	checksum: Fnv
+}
+
+impl<'w, W: Write> Write for ChecksumWriter<'w, W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let written = self.writer.write(buf)?;
+		self.checksum.write(&buf[..written]);
+		Ok(written)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+}
@@ -0,0 +1,121 @@
+use super::{ Pool, SlicesWrap };
+
+/// Wraps another pool, storing strings up to `N` bytes long inline in the raw
+/// value itself instead of interning them. Short strings never touch the
+/// wrapped pool, so creating and cloning them doesn't need to take any locks
+/// or touch any refcounts. Strings longer than `N` bytes are passed through to
+/// the wrapped pool as usual.
+///
+/// Whether a string is stored inline or not only depends on its length, so two
+/// strings with the same contents will always have the same representation.
+///
+/// The default of 22 bytes is chosen so that, wrapping [`GlobalPool`](super::GlobalPool),
+/// the raw value is the same size as std's `String`.
+#[derive(Clone, Debug, Default)]
+pub struct SsoPool<P, const N: usize = 22> {
+	pool: P
+}
+
+#[cfg(test)]
+#[path = "../tests/sso_pool.rs"]
+mod tests;
+
+impl<P: Pool, const N: usize> SsoPool<P, N> {
+	/// Inline length is stored in a `u8`, evaluated (and checked) wherever we
+	/// create an inline raw
+	const N_FITS_IN_U8: () = assert!(N <= u8::MAX as usize, "SsoPool can only store up to 255 bytes inline");
+
+	pub fn new(pool: P) -> Self {
+		Self { pool }
+	}
+
+	/// Returns a reference to the wrapped pool
+	pub fn inner(&self) -> &P {
+		&self.pool
+	}
+}
+
+/// Raw value of an [`SsoPool`]
+#[derive(Debug)]
+pub enum SsoRaw<R, const N: usize> {
+	Inline { len: u8, bytes: [u8; N] },
+	Pooled(R)
+}
+
+impl<R, const N: usize> SsoRaw<R, N> {
+	pub fn is_inline(&self) -> bool {
+		matches!(self, Self::Inline { .. })
+	}
+}
+
+impl<P: Pool, const N: usize> Pool for SsoPool<P, N> {
+	type Raw = SsoRaw<P::Raw, N>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		#[allow(clippy::let_unit_value)]
+		let () = Self::N_FITS_IN_U8;
+		if slices.len() > N { return SsoRaw::Pooled(self.pool.raw_from_slices(slices)) }
+
+		let mut bytes = [0u8; N];
+		let mut len = 0;
+		for slice in slices.0 {
+			bytes[len..len + slice.len()].copy_from_slice(slice);
+			len += slice.len();
+		}
+
+		SsoRaw::Inline { len: len as u8, bytes }
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		match raw {
+			SsoRaw::Inline { len, bytes } => { &bytes[..*len as usize] }
+			SsoRaw::Pooled(raw) => { self.pool.raw_to_slice(raw) }
+		}
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		if slices.len() > N {
+			self.pool.raw_get(slices).map(SsoRaw::Pooled)
+		} else {
+			// inline strings don't need to be looked up, creating one doesn't
+			// insert anything anywhere. SAFETY: this just copies the bytes, so it
+			// doesn't matter here if they aren't valid UTF-8
+			Some(unsafe { self.raw_from_slices(slices) })
+		}
+	}
+
+	unsafe fn raw_from_vec(&self, vec: Vec<u8>) -> Self::Raw {
+		if vec.len() > N {
+			SsoRaw::Pooled(self.pool.raw_from_vec(vec))
+		} else {
+			self.raw_from_slice(&vec)
+		}
+	}
+
+	fn raw_empty(&self) -> Self::Raw {
+		#[allow(clippy::let_unit_value)]
+		let () = Self::N_FITS_IN_U8;
+		SsoRaw::Inline { len: 0, bytes: [0u8; N] }
+	}
+
+	fn raw_into_vec(&self, raw: Self::Raw) -> Vec<u8> {
+		match raw {
+			SsoRaw::Inline { len, bytes } => { bytes[..len as usize].to_vec() }
+			SsoRaw::Pooled(raw) => { self.pool.raw_into_vec(raw) }
+		}
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		match raw {
+			SsoRaw::Inline { len, bytes } => { SsoRaw::Inline { len: *len, bytes: *bytes } }
+			SsoRaw::Pooled(raw) => { SsoRaw::Pooled(self.pool.raw_clone(raw)) }
+		}
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		match raw {
+			SsoRaw::Inline { .. } => { None }
+			SsoRaw::Pooled(raw) => { self.pool.raw_precomputed_hash(raw) }
+		}
+	}
+}
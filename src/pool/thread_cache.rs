@@ -0,0 +1,52 @@
+//! Per-thread cache in front of [`GlobalPool`](super::GlobalPool), enabled with
+//! the `thread-cache` feature.
+//!
+//! The cache is a small direct-mapped table, indexed by the low bits of the
+//! hash, holding clones of entries from the global pool. A hit returns the
+//! cached entry without touching the shared set (or its lock) at all. A miss
+//! goes to the shared set, and replaces whatever was in that slot.
+//!
+//! The global pool never removes entries, so a cached entry is always the same
+//! allocation the shared set would have returned, and never needs invalidating.
+
+use super::SlicesWrap;
+use super::global::SliceHashWrap;
+use ::std::cell::RefCell;
+use ::std::sync::Arc;
+
+/// Number of slots in each thread's cache. Must be a power of two.
+const SLOTS: usize = 1024;
+
+#[cfg(test)]
+#[path = "../tests/thread_cache.rs"]
+mod tests;
+
+thread_local! {
+	static CACHE: RefCell<Box<[Option<Arc<SliceHashWrap>>]>> = RefCell::new(vec![None; SLOTS].into_boxed_slice());
+}
+
+/// Returns the cached entry for `slices` if there is one, otherwise calls `f`
+/// and caches what it returns.
+pub(crate) fn get_or_insert_with<F>(hash: u64, slices: &SlicesWrap, f: F) -> Option<Arc<SliceHashWrap>>
+where
+	F: FnOnce() -> Option<Arc<SliceHashWrap>>
+{
+	let slot = hash as usize & (SLOTS - 1);
+
+	let cached = CACHE.try_with(|cache| {
+		cache.borrow()[slot].as_ref()
+			.filter(|raw| raw.matches(hash, slices))
+			.cloned()
+	});
+
+	match cached {
+		Ok(Some(raw)) => { Some(raw) }
+		Ok(None) => {
+			let raw = f()?;
+			let _ = CACHE.try_with(|cache| cache.borrow_mut()[slot] = Some(Arc::clone(&raw)));
+			Some(raw)
+		}
+		// the thread is shutting down and the cache is already gone
+		Err(_) => { f() }
+	}
+}
@@ -0,0 +1,117 @@
+use super::{ Pool, SlicesWrap };
+use super::global::SliceHashWrap;
+use super::index::{ self, Index };
+use ::parking_lot::Mutex;
+use ::std::fmt::{ self, Debug };
+use ::std::sync::Arc;
+use ::std::time::{ Duration, Instant };
+
+/// A pool that remembers when each string was last interned, so entries that
+/// haven't been interned in a while can be removed with
+/// [`sweep_expired`](TtlPool::sweep_expired).
+///
+/// The time is taken from a [`Clock`], which is [`SystemClock`] by default.
+///
+/// Clones of a `TtlPool` share the same index.
+#[derive(Clone)]
+pub struct TtlPool<C = SystemClock> {
+	clock: C,
+	inner: Arc<Mutex<TtlInner>>
+}
+
+#[cfg(test)]
+#[path = "../tests/ttl_pool.rs"]
+mod tests;
+
+struct TtlInner {
+	ttl: Duration,
+	/// entries, with the time they were last interned
+	index: Index<Instant>
+}
+
+/// Source of the current time for a [`TtlPool`]
+pub trait Clock: Clone {
+	fn now(&self) -> Instant;
+}
+
+/// [`Clock`] that returns [`Instant::now`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+impl TtlPool {
+	pub fn new(ttl: Duration) -> Self {
+		Self::with_clock(ttl, SystemClock)
+	}
+}
+
+impl<C: Clock> TtlPool<C> {
+	pub fn with_clock(ttl: Duration, clock: C) -> Self {
+		let inner = TtlInner { ttl, index: Index::new() };
+		Self { clock, inner: Arc::new(Mutex::new(inner)) }
+	}
+
+	pub fn ttl(&self) -> Duration {
+		self.inner.lock().ttl
+	}
+
+	/// Number of strings currently in the index
+	pub fn len(&self) -> usize {
+		self.inner.lock().index.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Removes strings from the index that were last interned at least the
+	/// TTL before `now`, and aren't referenced by anything other than the pool.
+	/// Returns how many were removed.
+	///
+	/// Expired strings that are still in use stay in the index, and will be
+	/// removed by a later sweep once they aren't in use anymore (as long as
+	/// they don't get interned again in the meantime).
+	pub fn sweep_expired(&self, now: Instant) -> usize {
+		let mut inner = self.inner.lock();
+		let TtlInner { ttl, index } = &mut *inner;
+		index.remove_unused(|last| now.saturating_duration_since(*last) >= *ttl)
+	}
+}
+
+impl<C: Debug> Debug for TtlPool<C> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let inner = self.inner.lock();
+		f.debug_struct("TtlPool")
+			.field("len", &inner.index.len())
+			.field("ttl", &inner.ttl)
+			.field("clock", &self.clock)
+			.finish()
+	}
+}
+
+impl<C: Clock> Pool for TtlPool<C> {
+	type Raw = Arc<SliceHashWrap>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let now = self.clock.now();
+		let mut inner = self.inner.lock();
+
+		let hash = inner.index.hash(&slices);
+		let (raw, last, _) = inner.index.get_or_insert_with(hash, &slices, || now);
+		*last = now;
+
+		Arc::clone(raw)
+	}
+
+	fn raw_get(&self, slices: SlicesWrap) -> Option<Self::Raw> {
+		// looking up doesn't count as interning, so doesn't refresh the entry
+		self.inner.lock().index.get_raw(&slices)
+	}
+
+	index::impl_index_raw!();
+}
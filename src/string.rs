@@ -2,6 +2,7 @@ use crate::pool::{ GlobalPool, Pool, SlicesWrap };
 use ::std::borrow::Borrow;
 use ::std::ffi::OsStr;
 use ::std::fmt::{ self, Debug, Display };
+use ::std::hash::{ Hash, Hasher };
 use ::std::ops::{ Add, AddAssign, Bound, Deref, RangeBounds };
 use ::std::path::Path;
 use ::std::string::{ self as std_string, String as StdString };
@@ -16,6 +17,48 @@ pub struct String<P: Pool = GlobalPool> {
 #[path = "./tests/string.rs"]
 mod tests;
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(any(test, feature = "proptest"))]
+pub mod proptest;
+#[cfg(feature = "rkyv")]
+mod rkyv;
+#[cfg(feature = "rkyv")]
+pub use self::rkyv::{ ArchivedString, InternDeserializer, PooledStringFlavor };
+#[cfg(feature = "serde")]
+mod serde;
+
+/// Interns strings into the provided pool when deserialising them, as a serde
+/// `DeserializeSeed` (with the `serde` feature) or an rkyv deserialiser (with
+/// the `rkyv` feature)
+#[derive(Clone, Debug)]
+pub struct InternIn<P>(pub P);
+
+/// Strings the `arbitrary` and `proptest` generators pick from (besides fully
+/// random ones), also used by [`pool::conformance`](crate::pool::conformance).
+/// Kept small, so generated strings are often duplicates of each other, and
+/// pools actually get to share allocations.
+pub(crate) const SAMPLES: &[&str] = &[
+	"",
+	"a",
+	"abc",
+	"string pool",
+	" \t\n",
+	"\0",
+	"é",
+	"e\u{301}",
+	"ß",
+	"Ω≈ç√",
+	"日本語",
+	"🦀",
+	"👩‍👩‍👧",
+	"🇳🇿",
+	"\u{feff}",
+	"\u{200b}",
+	"\u{10ffff}",
+	"Ａ"
+];
+
 /// constructors in default pool
 impl String {
 	pub fn new() -> Self {
@@ -114,6 +157,16 @@ impl<P: Pool> String<P> {
 		Self { raw, pool }
 	}
 
+	/// Creates a string from a raw value and the pool it came from.
+	///
+	/// # Safety
+	///
+	/// `raw` must have been created by `pool` (or a clone of it), and must
+	/// contain valid UTF-8.
+	pub unsafe fn from_raw_in(raw: P::Raw, pool: P) -> Self {
+		Self { raw, pool }
+	}
+
 	pub fn to_other_pool<P2: Pool>(&self, pool: P2) -> String<P2> {
 		let slice = self.pool.raw_to_slice(&self.raw);
 		let raw = unsafe { pool.raw_from_slice(slice) };
@@ -133,6 +186,16 @@ impl<P: Pool> String<P> {
 
 /// methods that work with any pool
 impl<P: Pool> String<P> {
+	/// Returns a reference to the raw value backing this string
+	pub fn raw(&self) -> &P::Raw {
+		&self.raw
+	}
+
+	/// Returns a reference to the pool this string is stored in
+	pub fn pool(&self) -> &P {
+		&self.pool
+	}
+
 	pub fn into_bytes(self) -> Vec<u8> {
 		self.pool.raw_into_vec(self.raw)
 	}
@@ -171,6 +234,16 @@ impl<P: Pool> String<P> {
 		self.pool.raw_to_slice(&self.raw)
 	}
 
+	/// Returns the hash the pool computed for this string when it was interned,
+	/// if the pool stores one (see [`Pool::raw_precomputed_hash`]). Strings with
+	/// the same contents in the same pool have the same precomputed hash, so
+	/// this can be fed into a pass-through hasher for use as a map key.
+	///
+	/// Note that this is _not_ the same value the [`Hash`] impl produces.
+	pub fn precomputed_hash(&self) -> Option<u64> {
+		self.pool.raw_precomputed_hash(&self.raw)
+	}
+
 	pub fn truncate(&mut self, new_len: usize) {
 		if new_len > self.len() { return }
 
@@ -535,7 +608,14 @@ impl<P: Pool> From<(&str, P)> for String<P> {
 // impl<P: Pool> FromIterator<char> for String<P> {}
 
 // impl<P: Pool> FromStr for String<P> {}
-// impl<P: Pool> Hash for String<P> {}
+
+impl<P: Pool> Hash for String<P> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		// same as std String, so that this is consistent with Borrow<str>,
+		// and consistent across pools (no matter how the raw is stored)
+		self.as_str().hash(state)
+	}
+}
 
 // impl<P: Pool> Index<Range<usize>> for String<P> {}
 // impl<P: Pool> Index<RangeFull> for String<P> {}
@@ -545,13 +625,39 @@ impl<P: Pool> From<(&str, P)> for String<P> {
 
 // impl Ord for String {}
 
-// impl<'h, P: Pool> PartialEq<&'h str> for String<P> {}
+impl<'h, P: Pool> PartialEq<&'h str> for String<P> {
+	fn eq(&self, other: &&'h str) -> bool {
+		self.as_str() == *other
+	}
+}
+
 // impl<'h, P: Pool> PartialEq<Cow<'h, str>> for String<P> {}
-// impl<'h, P: Pool> PartialEq<String<P>> for &'h str {}
+
+impl<'h, P: Pool> PartialEq<String<P>> for &'h str {
+	fn eq(&self, other: &String<P>) -> bool {
+		*self == other.as_str()
+	}
+}
+
 // impl<'h, P: Pool> PartialEq<String<P>> for Cow<'h, str> {}
-// impl<P: Pool> PartialEq<String<P>> for str {}
-// impl<P: Pool> PartialEq<str> for String<P> {}
-// impl<P: Pool> PartialEq for String<P> {}
+
+impl<P: Pool> PartialEq<String<P>> for str {
+	fn eq(&self, other: &String<P>) -> bool {
+		self == other.as_str()
+	}
+}
+
+impl<P: Pool> PartialEq<str> for String<P> {
+	fn eq(&self, other: &str) -> bool {
+		self.as_str() == other
+	}
+}
+
+impl<P: Pool, P2: Pool> PartialEq<String<P2>> for String<P> {
+	fn eq(&self, other: &String<P2>) -> bool {
+		self.as_bytes() == other.as_bytes()
+	}
+}
 
 // impl<P: Pool> PartialOrd for String<P> {}
 
@@ -559,6 +665,6 @@ impl<P: Pool> From<(&str, P)> for String<P> {
 // impl<P: Pool> ToSocketAddrs for String<P> {}
 // impl<P: Pool> Write for String<P> {}
 
-// impl<P: Pool> Eq for String<P> {}
+impl<P: Pool> Eq for String<P> {}
 // impl<P: Pool> StructuralEq for String<P> {}
 // impl<P: Pool> StructuralPartialEq for String<P> {}
@@ -0,0 +1,30 @@
+//! arbitrary support, enabled with the `arbitrary` feature
+//!
+//! Strings are interned into the default instance of the pool. Besides fully
+//! arbitrary strings, they are often empty or picked from a small set of
+//! samples (with interesting Unicode in them), so that a collection of
+//! generated strings usually contains duplicates.
+
+use super::{ SAMPLES, String };
+use crate::pool::Pool;
+use ::arbitrary::{ Arbitrary, Result, Unstructured };
+
+impl<'a, P: Pool + Default> Arbitrary<'a> for String<P> {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		let s = match u.int_in_range(0u8..=3)? {
+			0 => { "" }
+			1 | 2 => { *u.choose(SAMPLES)? }
+			_ => { <&str>::arbitrary(u)? }
+		};
+
+		Ok(Self::from_str_in(s, P::default()))
+	}
+
+	fn size_hint(depth: usize) -> (usize, Option<usize>) {
+		(1, None)
+	}
+}
+
+#[cfg(test)]
+#[path = "../tests/arbitrary.rs"]
+mod tests;
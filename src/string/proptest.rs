@@ -0,0 +1,70 @@
+//! proptest strategies, enabled with the `proptest` feature
+//!
+//! Besides fully random strings, the strategies here often generate empty
+//! strings or pick from a small set of samples (with interesting Unicode in
+//! them), so that a collection of generated strings usually contains
+//! duplicates, and pools actually get to share allocations.
+//!
+//! ```
+//! # use string_pool::String;
+//! # use string_pool::pool::ArenaPool;
+//! # use string_pool::string::proptest::string_in;
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     fn round_trips(s in any::<String>()) {
+//!         prop_assert_eq!(String::from(s.as_str()), s);
+//!     }
+//!
+//!     fn custom_pool(s in string_in(ArenaPool::new())) {
+//!         prop_assert!(s.len() < 1024);
+//!     }
+//! }
+//! # round_trips();
+//! # custom_pool();
+//! ```
+
+use super::{ SAMPLES, String };
+use crate::pool::Pool;
+use ::proptest::prelude::*;
+use ::proptest::sample::select;
+use ::std::fmt::Debug;
+use ::std::string::String as StdString;
+
+/// Generates std strings, the same way the other strategies in this module
+/// generate pooled ones. Useful for generating the contents of a string, to
+/// compare against std `String`.
+pub fn std_string() -> impl Strategy<Value = StdString> {
+	prop_oneof![
+		1 => Just(StdString::new()),
+		3 => select(SAMPLES).prop_map(StdString::from),
+		3 => any::<StdString>()
+	]
+}
+
+/// Generates strings interned into `pool`
+pub fn string_in<P>(pool: P) -> impl Strategy<Value = String<P>>
+where
+	P: Pool + Debug,
+	P::Raw: Debug
+{
+	std_string().prop_map(move |s| String::from_str_in(&s, pool.clone()))
+}
+
+/// Generates strings interned into the default instance of the pool
+impl<P> Arbitrary for String<P>
+where
+	P: Pool + Default + Debug + 'static,
+	P::Raw: Debug
+{
+	type Parameters = ();
+	type Strategy = BoxedStrategy<Self>;
+
+	fn arbitrary_with(_: ()) -> Self::Strategy {
+		string_in(P::default()).boxed()
+	}
+}
+
+#[cfg(test)]
+#[path = "../tests/proptest.rs"]
+mod tests;
@@ -0,0 +1,82 @@
+//! rkyv support, enabled with the `rkyv` feature
+//!
+//! Strings are archived as shared pointers (like `Rc<str>`), keyed on their
+//! pooled allocation. Strings sharing an allocation, which for deduplicating
+//! pools means every equal string in that pool, are only written to the
+//! archive once.
+//!
+//! When deserialising, strings are interned into a pool again: the default
+//! instance of the pool when deserialising with [`rkyv::Infallible`], or the
+//! provided pool when deserialising with [`InternIn`].
+
+use super::{ InternIn, String };
+use crate::pool::Pool;
+use ::rkyv::{ Archive, Deserialize, Fallible, Serialize, SerializeUnsized };
+use ::rkyv::rc::{ ArchivedRc, RcResolver };
+use ::rkyv::ser::{ Serializer, SharedSerializeRegistry };
+
+/// Archived [`String`], which points to its (shared) contents
+pub type ArchivedString = ArchivedRc<str, PooledStringFlavor>;
+
+/// Flavor of [`ArchivedString`]'s shared pointer, so it can't be mixed up with
+/// other kinds of shared pointers when validating an archive
+pub struct PooledStringFlavor;
+
+/// A deserialiser that knows which pool to intern strings into
+pub trait InternDeserializer<P>: Fallible {
+	fn pool(&self) -> P;
+}
+
+impl<P: Pool + Default> InternDeserializer<P> for ::rkyv::Infallible {
+	fn pool(&self) -> P {
+		P::default()
+	}
+}
+
+impl<P> Fallible for InternIn<P> {
+	type Error = ::std::convert::Infallible;
+}
+
+impl<P: Pool> InternDeserializer<P> for InternIn<P> {
+	fn pool(&self) -> P {
+		self.0.clone()
+	}
+}
+
+#[cfg(test)]
+#[path = "../tests/rkyv.rs"]
+mod tests;
+
+impl<P: Pool> String<P> {
+	/// The contents, used as the key for deduplicating strings in the archive
+	fn shared_key(&self) -> &str {
+		// pools that pack strings together could have an empty string start at
+		// the same address as the next string, so don't key those on the pool
+		if self.is_empty() { "" } else { self.as_str() }
+	}
+}
+
+impl<P: Pool> Archive for String<P> {
+	type Archived = ArchivedString;
+	type Resolver = RcResolver<<str as ::rkyv::ArchiveUnsized>::MetadataResolver>;
+
+	unsafe fn resolve(&self, pos: usize, resolver: Self::Resolver, out: *mut Self::Archived) {
+		ArchivedRc::resolve_from_ref(self.shared_key(), pos, resolver, out);
+	}
+}
+
+impl<P: Pool, S> Serialize<S> for String<P>
+where
+	str: SerializeUnsized<S>,
+	S: Serializer + SharedSerializeRegistry + ?Sized
+{
+	fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+		ArchivedString::serialize_from_ref(self.shared_key(), serializer)
+	}
+}
+
+impl<P: Pool, D: InternDeserializer<P> + ?Sized> Deserialize<String<P>, D> for ArchivedString {
+	fn deserialize(&self, deserializer: &mut D) -> Result<String<P>, D::Error> {
+		Ok(String::from_str_in(self.get(), deserializer.pool()))
+	}
+}
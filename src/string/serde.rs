@@ -0,0 +1,83 @@
+//! serde support, enabled with the `serde` feature
+//!
+//! Strings are serialised as plain strings. When deserialising, they are
+//! interned straight from the deserialiser's input if it can lend out a
+//! `&str` (for example, an unescaped string in JSON being deserialised from a
+//! `&str`), without allocating a std `String` first.
+
+use super::{ InternIn, String };
+use crate::pool::Pool;
+use ::serde::{ Deserialize, Deserializer, Serialize, Serializer };
+use ::serde::de::{ self, DeserializeSeed, Unexpected, Visitor };
+use ::std::fmt;
+use ::std::string::String as StdString;
+
+impl<P: Pool> Serialize for String<P> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(self.as_str())
+	}
+}
+
+/// Deserialises into the default instance of the pool. For pools that don't
+/// implement [`Default`], use [`InternIn`].
+impl<'de, P: Pool + Default> Deserialize<'de> for String<P> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		InternIn(P::default()).deserialize(deserializer)
+	}
+}
+
+/// Deserialises a string, interning it in the provided pool
+///
+/// ```
+/// # use string_pool::pool::ArenaPool;
+/// # use string_pool::string::InternIn;
+/// use serde::de::DeserializeSeed;
+///
+/// let pool = ArenaPool::new();
+/// let mut deserializer = serde_json::Deserializer::from_str(r#""interned""#);
+/// let s = InternIn(pool.clone()).deserialize(&mut deserializer).unwrap();
+///
+/// assert_eq!(s, "interned");
+/// assert_eq!(pool.len(), 1);
+/// ```
+impl<'de, P: Pool> DeserializeSeed<'de> for InternIn<P> {
+	type Value = String<P>;
+
+	fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<String<P>, D::Error> {
+		deserializer.deserialize_str(StringVisitor(self.0))
+	}
+}
+
+#[cfg(test)]
+#[path = "../tests/serde.rs"]
+mod tests;
+
+struct StringVisitor<P>(P);
+
+impl<'de, P: Pool> Visitor<'de> for StringVisitor<P> {
+	type Value = String<P>;
+
+	fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("a string")
+	}
+
+	fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<String<P>, E> {
+		Ok(String::from_str_in(v, self.0))
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<String<P>, E> {
+		Ok(String::from_str_in(v, self.0))
+	}
+
+	fn visit_string<E: de::Error>(self, v: StdString) -> Result<String<P>, E> {
+		// the pool might be able to reuse the allocation
+		Ok(unsafe { String::from_utf8_unchecked_in(v.into_bytes(), self.0) })
+	}
+
+	fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<String<P>, E> {
+		match std::str::from_utf8(v) {
+			Ok(s) => { Ok(String::from_str_in(s, self.0)) }
+			Err(_) => { Err(E::invalid_value(Unexpected::Bytes(v), &self)) }
+		}
+	}
+}
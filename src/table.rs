@@ -0,0 +1,185 @@
+//! String tables, for serialising lots of (mostly repeated) strings compactly.
+//!
+//! Instead of writing out every string in full, a [`StringTableEncoder`]
+//! collects every distinct string, and strings are written as varint indices
+//! into the table. The table itself is written separately, containing each
+//! distinct string once. On the other end, a [`StringTableDecoder`] reads the
+//! table, interning every string in it into a pool, so equal strings share
+//! their allocation again, and then resolves the indices back into strings.
+//!
+//! The table is written as a varint count, followed by each string's length
+//! in bytes as a varint, followed by its bytes. Varints are LEB128 encoded.
+//!
+//! ```
+//! # use string_pool::String;
+//! # use string_pool::pool::GlobalPool;
+//! # use string_pool::table::{ StringTableDecoder, StringTableEncoder };
+//! let strings = ["a", "b", "a", "a"].map(String::from);
+//!
+//! let mut encoder = StringTableEncoder::new();
+//! let mut refs = Vec::new();
+//! for s in &strings {
+//!     encoder.write_interned_ref(&mut refs, s).unwrap();
+//! }
+//! let mut table = Vec::new();
+//! encoder.write_table(&mut table).unwrap();
+//! assert_eq!(encoder.len(), 2);
+//!
+//! let decoder = StringTableDecoder::read(&*table, GlobalPool).unwrap();
+//! let mut refs = &*refs;
+//! for s in &strings {
+//!     assert_eq!(decoder.read_ref(&mut refs).unwrap(), *s);
+//! }
+//! ```
+
+use crate::collections::InternedMap;
+use crate::pool::{ Pool, SnapshotError };
+use crate::pool::snapshot::{ read_varint, write_varint };
+use crate::string::String;
+use ::hashbrown::HashMap;
+use ::std::fmt::{ self, Debug };
+use ::std::io::{ self, Read, Write };
+
+#[cfg(test)]
+#[path = "./tests/table.rs"]
+mod tests;
+
+/// Collects distinct strings into a table, assigning each one an index. See
+/// the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct StringTableEncoder {
+	by_content: HashMap<Box<str>, u64>,
+	/// Strings from the global pool are the same if and only if they are the
+	/// same allocation, so these can be found without hashing their contents
+	by_pointer: InternedMap<u64>
+}
+
+impl StringTableEncoder {
+	pub fn new() -> Self {
+		Self { by_content: HashMap::new(), by_pointer: InternedMap::new() }
+	}
+
+	/// Number of distinct strings in the table
+	pub fn len(&self) -> usize {
+		self.by_content.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.by_content.is_empty()
+	}
+
+	/// Adds a string to the table if it isn't in there already, returning its
+	/// index in the table
+	pub fn insert<P: Pool>(&mut self, s: &String<P>) -> u64 {
+		self.insert_str(s.as_str())
+	}
+
+	/// Like [`insert`](Self::insert), but takes a string from the global pool,
+	/// and finds it by pointer instead of by its contents
+	pub fn insert_interned(&mut self, s: &String) -> u64 {
+		if let Some(index) = self.by_pointer.get(s) { return *index }
+
+		let index = self.insert_str(s.as_str());
+		self.by_pointer.insert(s.clone(), index);
+		index
+	}
+
+	/// Like [`insert`](Self::insert), but takes a `&str`
+	pub fn insert_str(&mut self, s: &str) -> u64 {
+		if let Some(index) = self.by_content.get(s) { return *index }
+
+		let index = self.by_content.len() as u64;
+		self.by_content.insert(s.into(), index);
+		index
+	}
+
+	/// Adds a string to the table, and writes its index to `writer`
+	pub fn write_ref<W: Write, P: Pool>(&mut self, mut writer: W, s: &String<P>) -> io::Result<()> {
+		write_varint(&mut writer, self.insert(s))
+	}
+
+	/// Adds a string from the global pool to the table (see
+	/// [`insert_interned`](Self::insert_interned)), and writes its index to
+	/// `writer`
+	pub fn write_interned_ref<W: Write>(&mut self, mut writer: W, s: &String) -> io::Result<()> {
+		write_varint(&mut writer, self.insert_interned(s))
+	}
+
+	/// Writes the table itself to `writer`
+	pub fn write_table<W: Write>(&self, mut writer: W) -> io::Result<()> {
+		let mut strings = vec![""; self.by_content.len()];
+		self.by_content.iter().for_each(|(s, index)| strings[*index as usize] = s);
+
+		write_varint(&mut writer, strings.len() as u64)?;
+		for s in strings {
+			write_varint(&mut writer, s.len() as u64)?;
+			writer.write_all(s.as_bytes())?;
+		}
+
+		writer.flush()
+	}
+}
+
+/// Table of strings read from a table written by a [`StringTableEncoder`],
+/// interned into a pool. See the [module docs](self).
+pub struct StringTableDecoder<P: Pool> {
+	strings: Vec<String<P>>
+}
+
+impl<P: Pool> StringTableDecoder<P> {
+	/// Reads a table from `reader`, interning every string in it into `pool`.
+	/// Only reads the table, leaving anything after it in `reader` alone.
+	pub fn read<R: Read>(mut reader: R, pool: P) -> Result<Self, SnapshotError> {
+		let count = read_varint(&mut reader)?;
+
+		// not trusting `count` too much for the capacity
+		let mut strings = Vec::with_capacity(count.min(1024) as usize);
+		for index in 0..count as usize {
+			let len = read_varint(&mut reader)?;
+
+			let mut bytes = Vec::new();
+			(&mut reader).take(len).read_to_end(&mut bytes)?;
+			if (bytes.len() as u64) < len { return Err(SnapshotError::Truncated) }
+
+			let s = String::from_utf8_in(bytes, pool.clone())
+				.map_err(|_| SnapshotError::InvalidUtf8 { index })?;
+			strings.push(s);
+		}
+
+		Ok(Self { strings })
+	}
+
+	/// Number of strings in the table
+	pub fn len(&self) -> usize {
+		self.strings.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.strings.is_empty()
+	}
+
+	/// Returns the string at `index` in the table
+	pub fn get(&self, index: u64) -> Option<&String<P>> {
+		self.strings.get(usize::try_from(index).ok()?)
+	}
+
+	/// Reads an index written by [`StringTableEncoder::write_ref`] from
+	/// `reader`, returning the string in the table at that index
+	pub fn read_ref<R: Read>(&self, mut reader: R) -> Result<String<P>, SnapshotError> {
+		let index = read_varint(&mut reader)?;
+		self.get(index).cloned().ok_or(SnapshotError::Malformed)
+	}
+
+	/// Returns the strings in the table, in order of their index
+	pub fn into_strings(self) -> Vec<String<P>> {
+		self.strings
+	}
+}
+
+impl<P: Pool> Debug for StringTableDecoder<P> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_list()
+			.entries(self.strings.iter().map(|s| s.as_str()))
+			.finish()
+	}
+}
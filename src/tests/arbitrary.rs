@@ -0,0 +1,29 @@
+use super::*;
+use crate::pool::GlobalPool;
+use ::arbitrary::Arbitrary;
+
+#[test]
+fn generates_duplicates() {
+	let data = (0..4096u32)
+		.map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+		.collect::<Vec<_>>();
+	let mut u = Unstructured::new(&data);
+	let strings = (0..64)
+		.map(|_| String::<GlobalPool>::arbitrary(&mut u).unwrap())
+		.collect::<Vec<_>>();
+
+	let mut duplicates = 0;
+	for (i, a) in strings.iter().enumerate() {
+		for b in &strings[..i] {
+			assert_eq!(a == b, a.as_ptr() == b.as_ptr());
+			if a == b { duplicates += 1 }
+		}
+	}
+	assert!(duplicates > 0);
+}
+
+#[test]
+fn empty_input() {
+	let s = String::<GlobalPool>::arbitrary(&mut Unstructured::new(&[])).unwrap();
+	assert_eq!(s, "");
+}
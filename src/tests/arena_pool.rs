@@ -0,0 +1,184 @@
+use super::*;
+use crate::String;
+use ::std::thread;
+
+crate::pool_conformance_tests!(conformance, ArenaPool::new());
+crate::pool_conformance_tests!(conformance_small_chunks, ArenaPool::with_chunk_size(16));
+
+#[test]
+fn dedups() {
+	let pool = ArenaPool::new();
+	let a = String::from_str_in("dedup", pool.clone());
+	let b = String::from_str_in("dedup", pool.clone());
+	let c = String::from_str_in("not dedup", pool.clone());
+
+	assert_eq!(a, "dedup");
+	assert_eq!(c, "not dedup");
+	assert!(Arc::ptr_eq(&a.raw().chunk, &b.raw().chunk));
+	assert_eq!(a.raw().offset, b.raw().offset);
+	assert_ne!(a.raw().offset, c.raw().offset);
+	assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn packs_into_chunks() {
+	let pool = ArenaPool::with_chunk_size(64);
+
+	// 16 byte header + 8 bytes each, so 2 fit in one chunk
+	let a = String::from_str_in("aaaaaaaa", pool.clone());
+	let b = String::from_str_in("bbbbbbbb", pool.clone());
+	let c = String::from_str_in("cccccccc", pool.clone());
+
+	assert!(Arc::ptr_eq(&a.raw().chunk, &b.raw().chunk));
+	assert_eq!(b.raw().offset, a.raw().offset + 24);
+	assert!(!Arc::ptr_eq(&a.raw().chunk, &c.raw().chunk));
+	assert_eq!(c.raw().offset, 0);
+
+	// doesn't fit in a chunk, so gets its own
+	let long = "long string ".repeat(10);
+	let d = String::from_str_in(&long, pool.clone());
+	assert_eq!(d, &*long);
+	assert!(!Arc::ptr_eq(&c.raw().chunk, &d.raw().chunk));
+
+	// and the current chunk is still the same one
+	let e = String::from_str_in("e", pool.clone());
+	assert!(Arc::ptr_eq(&c.raw().chunk, &e.raw().chunk));
+}
+
+#[test]
+fn refcounts() {
+	let pool = ArenaPool::new();
+	let a = String::from_str_in("refcounts", pool.clone());
+	// one in index, one in a
+	assert_eq!(a.raw().refs(), 2);
+
+	let b = a.clone();
+	let c = String::from_str_in("refcounts", pool.clone());
+	assert_eq!(a.raw().refs(), 4);
+
+	drop(b);
+	drop(c);
+	assert_eq!(a.raw().refs(), 2);
+}
+
+#[test]
+fn chunks_freed_with_pool() {
+	let pool = ArenaPool::new();
+	let s = String::from_str_in("chunks freed", pool.clone());
+	let chunk = Arc::downgrade(&s.raw().chunk);
+
+	drop(pool);
+	assert!(chunk.upgrade().is_some());
+	assert_eq!(s, "chunks freed");
+
+	drop(s);
+	assert!(chunk.upgrade().is_none());
+}
+
+#[test]
+fn threads() {
+	let pool = ArenaPool::with_chunk_size(256);
+
+	let handles = (0..8)
+		.map(|_| {
+			let pool = pool.clone();
+			thread::spawn(move || {
+				(0..500)
+					.map(|i| String::from_str_in(&format!("string {i}"), pool.clone()))
+					.collect::<Vec<_>>()
+			})
+		})
+		.collect::<Vec<_>>();
+	let results = handles.into_iter()
+		.map(|h| h.join().unwrap())
+		.collect::<Vec<_>>();
+
+	assert_eq!(pool.len(), 500);
+	for strings in &results[1..] {
+		for (i, (a, b)) in results[0].iter().zip(strings).enumerate() {
+			assert_eq!(*a, *format!("string {i}"));
+			assert!(Arc::ptr_eq(&a.raw().chunk, &b.raw().chunk));
+			assert_eq!(a.raw().offset, b.raw().offset);
+		}
+	}
+}
+
+#[test]
+fn collect_garbage() {
+	let pool = ArenaPool::new();
+	let kept = String::from_str_in("kept", pool.clone());
+	let _ = String::from_str_in("collected", pool.clone());
+	assert_eq!(pool.len(), 2);
+
+	assert_eq!(pool.collect_garbage(), 1);
+	assert_eq!(pool.len(), 1);
+	assert_eq!(kept, "kept");
+
+	// "kept" is still in the index, so it's reused
+	let again = String::from_str_in("kept", pool.clone());
+	assert_eq!(again.raw().offset, kept.raw().offset);
+}
+
+#[test]
+fn compact() {
+	// each chunk fits 4 entries of 8 bytes
+	let pool = ArenaPool::with_chunk_size(96);
+
+	let strings = (0..16)
+		.map(|i| String::from_str_in(&format!("string{i:02}"), pool.clone()))
+		.collect::<Vec<_>>();
+	let chunks = strings.iter()
+		.step_by(4)
+		.map(|s| Arc::downgrade(&s.raw().chunk))
+		.collect::<Vec<_>>();
+
+	// nothing to compact, all chunks are full
+	assert_eq!(pool.compact(), 0);
+
+	// hold on to one string from the first chunk, and remove the odd ones
+	// from the pool, leaving every chunk half empty
+	let held = strings[0].clone();
+	drop(strings);
+	pool.inner.write().index.retain(|raw, _| raw.as_bytes()[7] % 2 == 0);
+	assert_eq!(pool.len(), 8);
+
+	// first chunk is pinned by `held`, last chunk is current, so the middle two
+	// get moved into one new chunk
+	assert_eq!(pool.compact(), 96);
+	assert!(chunks[0].upgrade().is_some());
+	assert!(chunks[1].upgrade().is_none());
+	assert!(chunks[2].upgrade().is_none());
+	assert!(chunks[3].upgrade().is_some());
+
+	assert_eq!(pool.len(), 8);
+	for i in (0..16).step_by(2) {
+		let s = format!("string{i:02}");
+		assert_eq!(String::from_str_in(&s, pool.clone()), &*s);
+	}
+	assert_eq!(pool.len(), 8);
+	assert_eq!(held, "string00");
+}
+
+#[test]
+fn compact_ignores_tail_slack() {
+	// 3 entries of 8 bytes fill 72 of 96 bytes, and the next one is too big
+	// for the rest, leaving 24 bytes at the end of the first chunk unused
+	let pool = ArenaPool::with_chunk_size(96);
+	let strings = ["string00", "string01", "string02", "too big for the rest"]
+		.map(|s| String::from_str_in(s, pool.clone()));
+	let first = Arc::downgrade(&strings[0].raw().chunk);
+	assert!(!Arc::ptr_eq(&strings[0].raw().chunk, &strings[3].raw().chunk));
+	drop(strings);
+
+	// every entry is still there, so nothing is wasted
+	assert_eq!(pool.compact(), 0);
+	assert!(first.upgrade().is_some());
+
+	// removing one isn't enough to bother, removing two is
+	pool.inner.write().index.retain(|raw, _| raw.as_bytes() != b"string00");
+	assert_eq!(pool.compact(), 0);
+	pool.inner.write().index.retain(|raw, _| raw.as_bytes() != b"string01");
+	assert_eq!(pool.compact(), 96);
+	assert!(first.upgrade().is_none());
+	assert_eq!(pool.len(), 2);
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::String;
+use crate::pool::ArenaPool;
+
+crate::pool_conformance_tests!(conformance, BoundedPool::new(ArenaPool::new(), 64, 1024));
+
+#[test]
+fn entry_limit() {
+	let pool = BoundedPool::new(ArenaPool::new(), 2, usize::MAX);
+
+	let a = String::from_str_in("a", pool.clone());
+	let b = String::from_str_in("b", pool.clone());
+	let c = String::from_str_in("c", pool.clone());
+
+	assert!(a.raw().is_pooled());
+	assert!(b.raw().is_pooled());
+	assert!(!c.raw().is_pooled());
+	assert_eq!(c, "c");
+	assert_eq!(pool.entries(), 2);
+	assert_eq!(pool.inner().len(), 2);
+	assert_eq!(pool.fallbacks(), 1);
+
+	// existing strings are still reused
+	let a2 = String::from_str_in("a", pool.clone());
+	assert!(a2.raw().is_pooled());
+	assert_eq!(pool.fallbacks(), 1);
+
+	let c2 = c.clone();
+	assert!(!c2.raw().is_pooled());
+	assert_eq!(c2, c);
+}
+
+#[test]
+fn byte_limit() {
+	let pool = BoundedPool::new(ArenaPool::new(), usize::MAX, 10);
+
+	let a = String::from_str_in("12345678", pool.clone());
+	let b = String::from_str_in("123", pool.clone());
+	let c = String::from_str_in("12", pool.clone());
+
+	assert!(a.raw().is_pooled());
+	assert!(!b.raw().is_pooled());
+	assert!(c.raw().is_pooled());
+	assert_eq!(pool.bytes(), 10);
+	assert_eq!(pool.fallbacks(), 1);
+	assert_eq!(b.into_bytes(), b"123");
+}
+
+#[test]
+fn already_in_pool() {
+	let arena = ArenaPool::new();
+	let _existing = String::from_str_in("existing", arena.clone());
+
+	let pool = BoundedPool::new(arena, 0, 0);
+	let s = String::from_str_in("existing", pool.clone());
+	assert!(s.raw().is_pooled());
+	assert_eq!(pool.fallbacks(), 0);
+}
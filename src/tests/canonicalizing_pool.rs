@@ -0,0 +1,66 @@
+use super::*;
+use crate::pool::ArenaPool;
+
+crate::pool_conformance_tests!(conformance, CanonicalizingPool::new(ArenaPool::new(), (Trim, AsciiLowercase)), canonicalizing);
+crate::pool_conformance_tests!(conformance_with_original, CanonicalizingPool::with_original(ArenaPool::new(), CollapseWhitespace), canonicalizing);
+
+#[test]
+fn canonicalizes() {
+	let pool = CanonicalizingPool::new(ArenaPool::new(), (Trim, AsciiLowercase));
+
+	let a = String::from_str_in("Foo", pool.clone());
+	let b = String::from_str_in(" foo ", pool.clone());
+	let c = String::from_str_in("FOO", pool.clone());
+
+	assert_eq!(a, "foo");
+	assert_eq!(a, b);
+	assert_eq!(a, c);
+	assert_eq!(pool.inner().len(), 1);
+	assert_eq!(a.original(), "foo");
+	assert!(a.raw().original().is_none());
+}
+
+#[test]
+fn keeps_original() {
+	let pool = CanonicalizingPool::with_original(ArenaPool::new(), AsciiLowercase);
+
+	let a = String::from_str_in("Foo", pool.clone());
+	let b = String::from_str_in("foo", pool.clone());
+
+	assert_eq!(a, b);
+	assert_eq!(a.original(), "Foo");
+	assert_eq!(a.clone().original(), "Foo");
+	assert_eq!(b.original(), "foo");
+	assert!(b.raw().original().is_none());
+	assert_eq!(pool.inner().len(), 1);
+}
+
+#[test]
+fn push_recanonicalizes() {
+	let pool = CanonicalizingPool::new(ArenaPool::new(), AsciiLowercase);
+
+	let mut s = String::from_str_in("Hello", pool.clone());
+	s.push_str(" World");
+	assert_eq!(s, "hello world");
+}
+
+#[test]
+fn collapse_whitespace() {
+	let canon = CollapseWhitespace;
+
+	assert!(matches!(canon.canonicalize("a b c"), Cow::Borrowed("a b c")));
+	assert_eq!(canon.canonicalize("a  b\t\nc"), "a b c");
+	assert_eq!(canon.canonicalize("\ta b "), " a b ");
+	assert_eq!((CollapseWhitespace, Trim).canonicalize("  a \t b  "), "a b");
+}
+
+#[test]
+fn get() {
+	let pool = CanonicalizingPool::new(ArenaPool::new(), AsciiLowercase);
+	let _s = String::from_str_in("get", pool.clone());
+
+	assert!(pool.raw_get(SlicesWrap(&[b"GET"])).is_some());
+	assert!(pool.raw_get(SlicesWrap(&[b"G", b"et"])).is_some());
+	assert!(pool.raw_get(SlicesWrap(&[b"got"])).is_none());
+	assert!(pool.raw_get(SlicesWrap(&[&[0xff]])).is_none());
+}
@@ -0,0 +1,103 @@
+use super::*;
+use ::std::sync::Arc;
+
+/// Generated by `keywords()`, checked by `generated_is_up_to_date`
+mod generated {
+	include!("codegen_keywords.rs");
+}
+use generated::*;
+
+fn keywords() -> Codegen {
+	let mut codegen = Codegen::new("KEYWORDS");
+	codegen
+		.prefix("KW_")
+		.crate_path("crate")
+		.words(["select", "from", "where", "group by", "order by", "select"]);
+	codegen
+}
+
+#[test]
+fn generated_is_up_to_date() {
+	assert_eq!(keywords().generate().unwrap(), include_str!("codegen_keywords.rs"));
+}
+
+#[test]
+fn generated_table() {
+	assert_eq!(KEYWORDS.len(), 5);
+	for word in ["select", "from", "where", "group by", "order by"] {
+		assert_eq!(KEYWORDS.get(word).unwrap().as_str(), word);
+	}
+	assert!(KEYWORDS.get("insert").is_none());
+	assert!(KEYWORDS.get("").is_none());
+	assert!(std::ptr::eq(KEYWORDS.get("group by").unwrap(), &KW_GROUP_BY));
+}
+
+#[test]
+fn static_strings_are_interned() {
+	let runtime = String::from(&*format!("{}{}", "sel", "ect"));
+	assert_eq!(*KW_SELECT, "select");
+	assert!(Arc::ptr_eq(KW_SELECT.raw(), runtime.raw()));
+	assert!(Arc::ptr_eq(KW_SELECT.raw(), KEYWORDS.get("select").unwrap().raw()));
+}
+
+#[test]
+fn perfect_hash() {
+	for len in [0, 1, 2, 10, 100, 1000] {
+		let words = (0..len).map(|i| format!("word {i}")).collect::<Vec<_>>();
+		let hash = PerfectHash::generate(&words).unwrap();
+
+		// leaking is fine in a test
+		let statics = words.iter()
+			.map(|word| &*Box::leak(Box::new(StaticString::new(Box::leak(word.clone().into_boxed_str())))))
+			.collect::<Vec<_>>();
+		let entries = hash.entries.iter().map(|&i| statics[i]).collect::<Vec<_>>();
+		let table = StaticTable::new(hash.seed, Box::leak(hash.displacements.into_boxed_slice()), Box::leak(entries.into_boxed_slice()));
+
+		assert_eq!(table.len(), len);
+		for (word, s) in words.iter().zip(&statics) {
+			assert!(std::ptr::eq(table.get(word).unwrap(), *s));
+		}
+		assert!(table.get("not a word").is_none());
+	}
+}
+
+#[test]
+fn static_names() {
+	let mut codegen = Codegen::new("T");
+	assert_eq!(codegen.static_name("foo-bar baz").unwrap(), "FOO_BAR_BAZ");
+	assert_eq!(codegen.static_name("1st").unwrap(), "_1ST");
+	assert_eq!(codegen.static_name("ü-").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+	assert_eq!(codegen.static_name("").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+	codegen.prefix("W_");
+	assert_eq!(codegen.static_name("1st").unwrap(), "W_1ST");
+	assert_eq!(codegen.static_name("").unwrap(), "W_");
+	assert_eq!(codegen.static_name("ü").unwrap(), "W__");
+
+	let err = codegen.words(["a-b", "a_b"]).generate().unwrap_err();
+	assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+	// clashing with the table's name
+	let err = Codegen::new("W_T").prefix("W_").word("t").generate().unwrap_err();
+	assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+	let err = Codegen::new("T").word("").generate().unwrap_err();
+	assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn perfect_hash_gives_up() {
+	// the same word twice always lands in the same slot
+	let words = ["same".to_owned(), "same".to_owned()];
+	assert!(PerfectHash::generate(&words).is_err());
+}
+
+#[test]
+fn many_words() {
+	let words = (0..10_000).map(|i| format!("word {i}")).collect::<Vec<_>>();
+	let mut codegen = Codegen::new("T");
+	codegen.prefix("W_").words(&words).words(&words);
+	assert_eq!(codegen.words.len(), 10_000);
+
+	let source = codegen.generate().unwrap();
+	assert!(source.contains("pub static W_WORD_9999:"));
+}
@@ -0,0 +1,9 @@
+// @generated by string_pool::codegen, do not edit
+
+pub static KW_SELECT: crate::codegen::StaticString = crate::codegen::StaticString::new("select");
+pub static KW_FROM: crate::codegen::StaticString = crate::codegen::StaticString::new("from");
+pub static KW_WHERE: crate::codegen::StaticString = crate::codegen::StaticString::new("where");
+pub static KW_GROUP_BY: crate::codegen::StaticString = crate::codegen::StaticString::new("group by");
+pub static KW_ORDER_BY: crate::codegen::StaticString = crate::codegen::StaticString::new("order by");
+
+pub static KEYWORDS: crate::codegen::StaticTable = crate::codegen::StaticTable::new(1, &[(4, 0)], &[&KW_GROUP_BY, &KW_WHERE, &KW_SELECT, &KW_FROM, &KW_ORDER_BY]);
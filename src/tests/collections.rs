@@ -0,0 +1,69 @@
+use super::*;
+use ::hashbrown::hash_map::DefaultHashBuilder;
+use ::std::hash::BuildHasher;
+
+#[test]
+fn map_lookups() {
+	let mut map = InternedMap::new();
+	assert!(map.insert(String::from("map lookups a"), 1).is_none());
+	assert!(map.insert(String::from("map lookups b"), 2).is_none());
+	assert_eq!(map.insert(String::from("map lookups a"), 3), Some(1));
+	assert_eq!(map.len(), 2);
+
+	assert_eq!(map.get("map lookups a"), Some(&3));
+	assert_eq!(map.get(&String::from("map lookups b")), Some(&2));
+	assert_eq!(map.get("map lookups not in pool"), None);
+
+	// in the pool, but not in the map
+	let _c = String::from("map lookups c");
+	assert_eq!(map.get("map lookups c"), None);
+
+	*map.get_mut("map lookups b").unwrap() += 10;
+	assert_eq!(map.remove("map lookups b"), Some(12));
+	assert!(!map.contains_key("map lookups b"));
+	assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn map_keys_are_pooled() {
+	let map = [("map keys are pooled", ())]
+		.into_iter()
+		.map(|(k, v)| (String::from(k), v))
+		.collect::<InternedMap<_>>();
+	let (key, _) = map.get_key_value("map keys are pooled").unwrap();
+
+	let s = String::from("map keys are pooled");
+	assert!(Arc::ptr_eq(key.raw(), s.raw()));
+}
+
+#[test]
+fn set() {
+	let mut set = InternedSet::new();
+	assert!(set.insert(String::from("interned set")));
+	assert!(!set.insert(String::from("interned set")));
+	assert!(set.contains("interned set"));
+	assert_eq!(set.get("interned set").unwrap(), "interned set");
+	assert!(set.remove("interned set"));
+	assert!(set.is_empty());
+}
+
+#[test]
+fn precomputed_hasher() {
+	let builder = BuildPrecomputedHasher::default();
+	assert_eq!(builder.hash_one(1234u64), 1234);
+
+	let s = String::from("precomputed hasher");
+	assert_eq!(builder.hash_one(KeyRef(s.raw())), s.precomputed_hash().unwrap());
+}
+
+#[test]
+fn precomputed_hasher_other_writes() {
+	let builder = BuildPrecomputedHasher::default();
+
+	// none of these write a single u64, and shouldn't panic doing so
+	assert_ne!(builder.hash_one("abc"), builder.hash_one("abd"));
+	assert_ne!(builder.hash_one("precomputed hasher"), builder.hash_one("precomputed hashes"));
+	assert_ne!(builder.hash_one([0u8; 3].as_slice()), builder.hash_one([0u8; 4].as_slice()));
+	assert_ne!(builder.hash_one((1u64, 2u64)), builder.hash_one((2u64, 1u64)));
+	assert_eq!(builder.hash_one(1u32), builder.hash_one(1u32));
+}
@@ -0,0 +1,87 @@
+use super::*;
+use crate::pool::GlobalPool;
+
+#[test]
+fn check_all() {
+	check(&GlobalPool);
+}
+
+/// Only interns the first slice, so it round-trips single slices fine, but
+/// loses the rest of split ones
+#[derive(Clone)]
+struct FirstSlicePool;
+
+impl Pool for FirstSlicePool {
+	type Raw = Box<[u8]>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		slices.0.first().copied().unwrap_or_default().into()
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw
+	}
+}
+
+#[test]
+#[should_panic = "raw_from_slices split at"]
+fn catches_split_slices() {
+	// single slices are fine
+	round_trip(&FirstSlicePool);
+	slices_intern_like_joined(&FirstSlicePool);
+}
+
+/// Gives cloned raws a different precomputed hash than the original
+#[derive(Clone)]
+struct BadHashPool;
+
+impl Pool for BadHashPool {
+	type Raw = (Box<[u8]>, u64);
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		(slices.to_boxed_slice(), 0)
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		&raw.0
+	}
+
+	fn raw_clone(&self, raw: &Self::Raw) -> Self::Raw {
+		(raw.0.clone(), 1)
+	}
+
+	fn raw_precomputed_hash(&self, raw: &Self::Raw) -> Option<u64> {
+		Some(raw.1)
+	}
+}
+
+#[test]
+#[should_panic = "raw_clone changed the precomputed hash"]
+fn catches_changed_hash() {
+	precomputed_hash(&BadHashPool);
+}
+
+/// Trims whitespace, so it doesn't round-trip, but is idempotent
+#[derive(Clone)]
+struct TrimPool;
+
+impl Pool for TrimPool {
+	type Raw = Box<str>;
+
+	unsafe fn raw_from_slices(&self, slices: SlicesWrap) -> Self::Raw {
+		let bytes = slices.to_boxed_slice();
+		str::from_utf8_unchecked(&bytes).trim().into()
+	}
+
+	fn raw_to_slice<'r>(&self, raw: &'r Self::Raw) -> &'r [u8] {
+		raw.as_bytes()
+	}
+}
+
+crate::pool_conformance_tests!(trim_pool, TrimPool, canonicalizing);
+
+#[test]
+#[should_panic = "raw_from_slice gave the wrong contents"]
+fn transforming_pools_dont_round_trip() {
+	round_trip(&TrimPool);
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::String;
+use crate::pool::{ ArenaPool, GlobalPool, LruPool };
+use ::std::sync::Arc;
+
+crate::pool_conformance_tests!(conformance, FallbackPool::new(LruPool::new(16), ArenaPool::new()));
+
+#[test]
+fn parent_first() {
+	let _builtin = String::from("fallback pool builtin");
+	let pool = FallbackPool::new(GlobalPool, ArenaPool::new());
+
+	let builtin = String::from_str_in("fallback pool builtin", pool.clone());
+	let plugin = String::from_str_in("fallback pool plugin", pool.clone());
+
+	assert!(builtin.raw().is_parent());
+	assert!(!plugin.raw().is_parent());
+	assert_eq!(plugin, "fallback pool plugin");
+
+	// parent wasn't inserted into
+	assert!(GlobalPool.get("fallback pool plugin").is_none());
+	assert_eq!(pool.child().len(), 1);
+
+	let plugin2 = String::from_str_in("fallback pool plugin", pool.clone());
+	assert!(!plugin2.raw().is_parent());
+	assert_eq!(pool.child().len(), 1);
+
+	assert!(pool.raw_get(SlicesWrap(&[b"fallback pool builtin"])).unwrap().is_parent());
+	assert!(!pool.raw_get(SlicesWrap(&[b"fallback pool plugin"])).unwrap().is_parent());
+	assert!(pool.raw_get(SlicesWrap(&[b"fallback pool neither"])).is_none());
+}
+
+#[test]
+fn dropping_child() {
+	let parent = ArenaPool::new();
+	let builtin = String::from_str_in("builtin", parent.clone());
+
+	let pool = FallbackPool::new(parent.clone(), LruPool::new(16));
+	let plugin = String::from_str_in("plugin", pool.clone());
+	let from_parent = String::from_str_in("builtin", pool.clone());
+
+	let FallbackRaw::Child(plugin_raw) = plugin.raw() else { panic!() };
+	let plugin_raw = Arc::downgrade(plugin_raw);
+
+	// every string holds a clone of the pool, so the child pool stays
+	// alive until the last string from it is dropped
+	drop(pool);
+	drop(from_parent);
+	assert!(plugin_raw.upgrade().is_some());
+	drop(plugin);
+	assert!(plugin_raw.upgrade().is_none());
+
+	assert_eq!(builtin, "builtin");
+	assert_eq!(parent.len(), 1);
+}
@@ -0,0 +1,59 @@
+use super::*;
+use crate::String;
+use crate::pool::{ ArenaPool, FallbackPool, GlobalPool };
+
+crate::pool_conformance_tests!(conformance, FrozenPool::new(["", "a", "abc", "日本語"]));
+
+#[test]
+fn new() {
+	let pool = FrozenPool::new(["select", "from", "where", "select"]);
+	assert_eq!(pool.len(), 3);
+
+	let select = pool.get("select").unwrap();
+	assert_eq!(select, "select");
+	assert!(Arc::ptr_eq(select.raw(), pool.get("select").unwrap().raw()));
+	assert!(pool.get("insert").is_none());
+
+	let mut strings = pool.iter().map(|s| s.as_str().to_owned()).collect::<Vec<_>>();
+	strings.sort();
+	assert_eq!(strings, ["from", "select", "where"]);
+}
+
+#[test]
+fn miss_does_not_insert() {
+	let pool = FrozenPool::new(["a"]);
+
+	let b1 = String::from_str_in("b", pool.clone());
+	let b2 = String::from_str_in("b", pool.clone());
+	assert_eq!(b1, b2);
+	assert!(!Arc::ptr_eq(b1.raw(), b2.raw()));
+	assert_eq!(pool.len(), 1);
+
+	let a1 = String::from_str_in("a", pool.clone());
+	let a2 = String::from_str_in("a", pool.clone());
+	assert!(Arc::ptr_eq(a1.raw(), a2.raw()));
+}
+
+#[test]
+fn freeze_global() {
+	let global = String::from("freeze global");
+	let frozen = GlobalPool.freeze();
+
+	let s = frozen.get("freeze global").unwrap();
+	assert!(Arc::ptr_eq(s.raw(), global.raw()));
+	assert_eq!(s.precomputed_hash(), global.precomputed_hash());
+
+	let _after = String::from("freeze global after");
+	assert!(frozen.get("freeze global after").is_none());
+}
+
+#[test]
+fn as_fallback_parent() {
+	let builtin = FrozenPool::new(["builtin"]);
+	let pool = FallbackPool::new(builtin, ArenaPool::new());
+
+	let a = String::from_str_in("builtin", pool.clone());
+	let b = String::from_str_in("plugin", pool.clone());
+	assert!(a.raw().is_parent());
+	assert!(!b.raw().is_parent());
+}
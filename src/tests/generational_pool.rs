@@ -0,0 +1,41 @@
+use super::*;
+use crate::String;
+
+crate::pool_conformance_tests!(conformance, GenerationalPool::new(2));
+
+fn contains(pool: &GenerationalPool, s: &str) -> bool {
+	pool.raw_get(SlicesWrap(&[s.as_bytes()])).is_some()
+}
+
+#[test]
+fn advance() {
+	let pool = GenerationalPool::new(1);
+
+	let _ = String::from_str_in("gen 0", pool.clone());
+	let _ = String::from_str_in("gen 0, reinterned in 1", pool.clone());
+	let in_use = String::from_str_in("gen 0, in use", pool.clone());
+
+	assert_eq!(pool.advance(), 0);
+	assert_eq!(pool.generation(), 1);
+	let _ = String::from_str_in("gen 0, reinterned in 1", pool.clone());
+	let _ = String::from_str_in("gen 1", pool.clone());
+
+	assert_eq!(pool.advance(), 1);
+	assert!(!contains(&pool, "gen 0"));
+	assert!(contains(&pool, "gen 0, reinterned in 1"));
+	assert!(contains(&pool, "gen 0, in use"));
+	assert!(contains(&pool, "gen 1"));
+
+	drop(in_use);
+	assert_eq!(pool.advance(), 3);
+	assert!(pool.is_empty());
+}
+
+#[test]
+fn keep_none() {
+	let pool = GenerationalPool::new(0);
+	let _ = String::from_str_in("a", pool.clone());
+
+	assert_eq!(pool.advance(), 1);
+	assert!(pool.is_empty());
+}
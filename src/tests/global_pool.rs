@@ -5,6 +5,8 @@ use ::std::string::String as StdString;
 use ::std::iter::repeat;
 use ::std::hash::BuildHasher;
 
+crate::pool_conformance_tests!(conformance, GlobalPool);
+
 #[test]
 fn slices_wrap_iter_hash_and_eq() {
 	let hash_builder = hashbrown::hash_map::DefaultHashBuilder::default();
@@ -20,7 +22,7 @@ fn slices_wrap_iter_hash_and_eq() {
 		let pool_strs = strs.iter()
 			.map(|s| &**s)
 			.collect::<String>();
-		let pool_strs = Arc::new(SliceHashWrap(pool_strs.into_bytes().into_boxed_slice()));
+		let pool_strs = SliceHashWrap::new(0, pool_strs.into_bytes().into_boxed_slice());
 
 		// create instance of SlicesWrap
 		let mut _slices = strs.iter()
@@ -57,8 +59,102 @@ fn rand_std_string() -> StdString {
 	vec.into_iter().collect()
 }
 
+#[allow(clippy::manual_hash_one)]
 fn hash_item<T: Hash>(hash_builder: &DefaultHashBuilder, item: &T) -> u64 {
 	let mut hasher = hash_builder.build_hasher();
 	item.hash(&mut hasher);
 	hasher.finish()
 }
+
+#[test]
+fn precomputed_hash() {
+	let a = crate::String::from("precomputed hash test");
+	let b = crate::String::from("precomputed hash test");
+	let c = crate::String::from("different precomputed hash test");
+
+	let hash = POOL.hash_builder().hash_one(SlicesWrap(&[b"precomputed hash test"]));
+	assert_eq!(a.precomputed_hash(), Some(hash));
+	assert_eq!(a.precomputed_hash(), b.precomputed_hash());
+	assert_ne!(a.precomputed_hash(), c.precomputed_hash());
+}
+
+#[test]
+fn growing_keeps_entries() {
+	let strs = (0..5000)
+		.map(|i| format!("growing keeps entries {i}"))
+		.collect::<Vec<_>>();
+	let pooled = strs.iter()
+		.map(|s| crate::String::from(&**s))
+		.collect::<Vec<_>>();
+
+	for (s, pooled) in strs.iter().zip(&pooled) {
+		let again = crate::String::from(&**s);
+		assert!(Arc::ptr_eq(again.raw(), pooled.raw()));
+	}
+}
+
+#[test]
+fn get_does_not_insert() {
+	assert!(GlobalPool.get("get does not insert").is_none());
+	assert!(GlobalPool.get("get does not insert").is_none());
+
+	let s = crate::String::from("get does not insert");
+	let got = GlobalPool.get("get does not insert").unwrap();
+	assert!(Arc::ptr_eq(s.raw(), got.raw()));
+}
+
+#[test]
+fn concurrent_interns_share_allocation() {
+	const THREADS: usize = 16;
+	const STRINGS: usize = 2000;
+
+	let handles = (0..THREADS)
+		.map(|t| std::thread::spawn(move || {
+			// every thread interns the same strings, in a different order
+			let mut strs = (0..STRINGS)
+				.map(|i| (i, crate::String::from(&*format!("concurrent intern {}", (i * 7 + t * 13) % STRINGS))))
+				.collect::<Vec<_>>();
+			strs.sort_by_key(|(i, s)| s.as_str().to_owned());
+			strs.into_iter().map(|(_, s)| s).collect::<Vec<_>>()
+		}))
+		.collect::<Vec<_>>();
+
+	let results = handles.into_iter()
+		.map(|h| h.join().unwrap())
+		.collect::<Vec<_>>();
+
+	for i in 0..STRINGS {
+		let first = &results[0][i];
+		for strs in &results[1..] {
+			assert_eq!(strs[i], *first);
+			assert!(Arc::ptr_eq(strs[i].raw(), first.raw()));
+		}
+	}
+}
+
+#[test]
+fn concurrent_from_str_in() {
+	const THREADS: usize = 8;
+	let barrier = std::sync::Barrier::new(THREADS);
+
+	for round in 0..20 {
+		// new strings every round, all threads starting at the same time
+		let results = std::thread::scope(|scope| {
+			let handles = (0..THREADS)
+				.map(|_| scope.spawn(|| {
+					barrier.wait();
+					(0..100)
+						.map(|i| crate::String::from_str_in(&format!("from_str_in {round} {i}"), GlobalPool))
+						.collect::<Vec<_>>()
+				}))
+				.collect::<Vec<_>>();
+			handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+		});
+
+		for strs in &results[1..] {
+			for (a, b) in strs.iter().zip(&results[0]) {
+				assert!(Arc::ptr_eq(a.raw(), b.raw()));
+			}
+		}
+	}
+}
@@ -0,0 +1,135 @@
+use super::*;
+use ::std::thread;
+
+fn intern(set: &LockFreeSet, hash: u64, s: &str) -> Arc<SliceHashWrap> {
+	set.intern_hashed(hash, &SlicesWrap(&[s.as_bytes()]))
+}
+
+#[test]
+fn intern_and_get() {
+	let set = LockFreeSet::new();
+
+	let hash = |slices: &SlicesWrap| set.hash_builder().hash_one(slices);
+
+	let slices = SlicesWrap(&[b"a", b"bc"]);
+	let a = set.intern_hashed(hash(&slices), &slices);
+	let slices = SlicesWrap(&[b"abc"]);
+	let b = set.intern_hashed(hash(&slices), &slices);
+	assert!(Arc::ptr_eq(&a, &b));
+	assert_eq!(a.as_bytes(), b"abc");
+
+	let slices = SlicesWrap(&[b"ab", b"c"]);
+	assert!(Arc::ptr_eq(&set.get_hashed(hash(&slices), &slices).unwrap(), &a));
+	let slices = SlicesWrap(&[b"abd"]);
+	assert!(set.get_hashed(hash(&slices), &slices).is_none());
+	assert_eq!(set.raws().len(), 1);
+}
+
+#[test]
+fn colliding_hashes() {
+	let set = LockFreeSet::new();
+	let strs = (0..100).map(|i| i.to_string()).collect::<Vec<_>>();
+
+	// every string lands on the same path, each one level deeper
+	let raws = strs.iter().map(|s| intern(&set, 0, s)).collect::<Vec<_>>();
+
+	for (s, raw) in strs.iter().zip(&raws) {
+		assert_eq!(raw.as_bytes(), s.as_bytes());
+		assert!(Arc::ptr_eq(&intern(&set, 0, s), raw));
+		assert!(Arc::ptr_eq(&set.get_hashed(0, &SlicesWrap(&[s.as_bytes()])).unwrap(), raw));
+	}
+
+	assert!(set.get_hashed(0, &SlicesWrap(&[b"missing"])).is_none());
+	assert_eq!(set.raws().len(), 100);
+}
+
+#[test]
+fn drop_releases_entries() {
+	let set = LockFreeSet::new();
+	let a = intern(&set, 1, "a");
+	let b = intern(&set, 1, "b");
+	assert_eq!(Arc::strong_count(&b), 2);
+
+	drop(set);
+	assert_eq!(Arc::strong_count(&a), 1);
+	assert_eq!(Arc::strong_count(&b), 1);
+}
+
+#[test]
+fn concurrent_colliding_interns() {
+	let set = LockFreeSet::new();
+
+	let results = thread::scope(|scope| {
+		let handles = (0..8)
+			.map(|_| scope.spawn(|| {
+				// few distinct hashes, so threads race on the same nodes
+				(0..500)
+					.map(|i| intern(&set, i % 4, &i.to_string()))
+					.collect::<Vec<_>>()
+			}))
+			.collect::<Vec<_>>();
+		handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+	});
+
+	for raws in &results[1..] {
+		for (a, b) in raws.iter().zip(&results[0]) {
+			assert!(Arc::ptr_eq(a, b));
+		}
+	}
+	assert_eq!(set.raws().len(), 500);
+}
+
+#[test]
+fn children_are_compact() {
+	let set = LockFreeSet::new();
+	intern(&set, 0, "root");
+
+	let guard = &epoch::pin();
+	assert!(set.root.children(guard).is_none());
+
+	// out of order, every child goes in the right place
+	for i in [5, 0, 15, 3] {
+		intern(&set, i, &format!("child {i}"));
+	}
+	let children = set.root.children(guard).unwrap();
+	assert_eq!(children.mask, 1 << 0 | 1 << 3 | 1 << 5 | 1 << 15);
+	assert_eq!(children.nodes.len(), 4);
+
+	for i in [0, 3, 5, 15] {
+		let child = set.root.child(i as usize, guard).unwrap();
+		let entry = unsafe { &*child.entry.load(Ordering::Acquire) };
+		assert_eq!(entry.as_bytes(), format!("child {i}").as_bytes());
+	}
+	assert!(set.root.child(1, guard).is_none());
+}
+
+#[test]
+fn concurrent_siblings() {
+	let set = LockFreeSet::new();
+
+	// different hashes, so threads race on adding different children to the
+	// same nodes, replacing each other's children arrays
+	let results = thread::scope(|scope| {
+		let handles = (0..8)
+			.map(|t| {
+				let set = &set;
+				scope.spawn(move || {
+					(0..1000u64)
+						.map(|i| (i + t * 125) % 1000)
+						.map(|i| (i, intern(set, i, &i.to_string())))
+						.collect::<Vec<_>>()
+				})
+			})
+			.collect::<Vec<_>>();
+		handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+	});
+
+	let mut first = results[0].clone();
+	first.sort_by_key(|(i, _)| *i);
+	for raws in &results[1..] {
+		for (i, raw) in raws {
+			assert!(Arc::ptr_eq(raw, &first[*i as usize].1));
+		}
+	}
+	assert_eq!(set.raws().len(), 1000);
+}
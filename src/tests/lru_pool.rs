@@ -0,0 +1,57 @@
+use super::*;
+use crate::String;
+
+crate::pool_conformance_tests!(conformance, LruPool::new(16));
+
+#[test]
+fn evicts_least_recently_interned() {
+	let pool = LruPool::new(2);
+
+	let a = String::from_str_in("a", pool.clone());
+	let b = String::from_str_in("b", pool.clone());
+	assert_eq!(pool.len(), 2);
+
+	// a is now more recent than b
+	let a2 = String::from_str_in("a", pool.clone());
+	assert!(Arc::ptr_eq(a.raw(), a2.raw()));
+
+	// evicts b
+	let c = String::from_str_in("c", pool.clone());
+	assert_eq!(pool.len(), 2);
+	assert!(pool.raw_get(SlicesWrap(&[b"a"])).is_some());
+	assert!(pool.raw_get(SlicesWrap(&[b"b"])).is_none());
+	assert!(pool.raw_get(SlicesWrap(&[b"c"])).is_some());
+
+	// evicted string still works, but interning it again makes a new copy
+	assert_eq!(b, "b");
+	let b2 = String::from_str_in("b", pool.clone());
+	assert_eq!(b, b2);
+	assert!(!Arc::ptr_eq(b.raw(), b2.raw()));
+
+	// which evicted a
+	assert!(pool.raw_get(SlicesWrap(&[b"a"])).is_none());
+	assert_eq!(pool.len(), 2);
+	assert_eq!(pool.inner.lock().order.len(), 2);
+}
+
+#[test]
+fn lookup_does_not_refresh() {
+	let pool = LruPool::new(2);
+	let _a = String::from_str_in("a", pool.clone());
+	let _b = String::from_str_in("b", pool.clone());
+
+	assert!(pool.raw_get(SlicesWrap(&[b"a"])).is_some());
+	let _c = String::from_str_in("c", pool.clone());
+	assert!(pool.raw_get(SlicesWrap(&[b"a"])).is_none());
+}
+
+#[test]
+fn zero_capacity() {
+	let pool = LruPool::new(0);
+	let a = String::from_str_in("a", pool.clone());
+	let a2 = String::from_str_in("a", pool.clone());
+
+	assert_eq!(a, a2);
+	assert!(!Arc::ptr_eq(a.raw(), a2.raw()));
+	assert!(pool.is_empty());
+}
@@ -0,0 +1,166 @@
+use super::*;
+use crate::String;
+use crate::pool::{ FallbackPool, GlobalPool };
+use ::std::fs;
+use ::std::path::PathBuf;
+use ::std::sync::atomic::{ AtomicUsize, Ordering };
+
+crate::pool_conformance_tests!(conformance, conformance_pool());
+crate::pool_conformance_tests!(conformance_fallback, FallbackPool::new(conformance_pool(), GlobalPool));
+
+/// Writes a table to a new file in the temp dir, removing it when dropped
+struct TableFile(PathBuf);
+
+impl TableFile {
+	fn new(name: &str, bytes: &[u8]) -> Self {
+		let path = std::env::temp_dir().join(format!("string-pool-{}-{name}", std::process::id()));
+		fs::write(&path, bytes).unwrap();
+		Self(path)
+	}
+
+	fn open(&self) -> Result<MappedPool, SnapshotError> {
+		unsafe { MappedPool::open(&self.0) }
+	}
+
+	fn open_unchecked(&self) -> Result<MappedPool, SnapshotError> {
+		unsafe { MappedPool::open_unchecked(&self.0) }
+	}
+}
+
+impl Drop for TableFile {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.0);
+	}
+}
+
+/// Maps a table with a few strings in it, in a file unique to the calling test
+/// (the file is removed again straight away, but stays mapped)
+fn conformance_pool() -> MappedPool {
+	static COUNTER: AtomicUsize = AtomicUsize::new(0);
+	let name = format!("conformance-{}", COUNTER.fetch_add(1, Ordering::Relaxed));
+
+	let file = TableFile::new(&name, &table(&["", "a", "abc", "日本語"]));
+	file.open().unwrap()
+}
+
+fn table(strings: &[&str]) -> Vec<u8> {
+	let mut builder = MappedPoolBuilder::new();
+	builder.extend(strings.iter().copied());
+
+	let mut buf = Vec::new();
+	builder.write(&mut buf).unwrap();
+	buf
+}
+
+#[test]
+fn lookup() {
+	let file = TableFile::new("lookup", &table(&["paris", "tokyo", "", "zürich", "paris"]));
+	let pool = file.open().unwrap();
+	assert_eq!(pool.len(), 4);
+
+	let paris = pool.get("paris").unwrap();
+	assert_eq!(paris, "paris");
+	assert!(paris.raw().is_pooled());
+	assert!(pool.get("").unwrap().raw().is_pooled());
+	assert_eq!(pool.get("zürich").unwrap(), "zürich");
+	assert!(pool.get("oslo").is_none());
+
+	// strings point into the mapping
+	let again = String::from_str_in("paris", pool.clone());
+	assert_eq!(paris.as_str().as_ptr(), again.as_str().as_ptr());
+	assert_eq!(paris.precomputed_hash(), again.precomputed_hash());
+	assert!(paris.precomputed_hash().is_some());
+}
+
+#[test]
+fn miss_is_owned() {
+	let file = TableFile::new("miss", &table(&["a"]));
+	let pool = file.open().unwrap();
+
+	let b = String::from_str_in("b", pool.clone());
+	assert_eq!(b, "b");
+	assert!(!b.raw().is_pooled());
+	assert!(b.precomputed_hash().is_none());
+}
+
+#[test]
+fn many() {
+	let strings = (0..5000).map(|i| format!("entry {i}")).collect::<Vec<_>>();
+	let file = TableFile::new("many", &table(&strings.iter().map(|s| &**s).collect::<Vec<_>>()));
+	let pool = file.open().unwrap();
+
+	assert_eq!(pool.len(), 5000);
+	for s in &strings {
+		assert_eq!(pool.get(s).unwrap(), **s);
+	}
+	assert!(pool.get("entry 5000").is_none());
+}
+
+#[test]
+fn with_fallback() {
+	let file = TableFile::new("fallback", &table(&["builtin"]));
+	let pool = FallbackPool::new(file.open().unwrap(), GlobalPool);
+
+	let builtin = String::from_str_in("builtin", pool.clone());
+	let other = String::from_str_in("mapped pool fallback", pool.clone());
+	assert!(builtin.raw().is_parent());
+	assert!(!other.raw().is_parent());
+}
+
+#[test]
+fn corrupt() {
+	let good = table(&["one", "two"]);
+
+	let open = |name: &str, bytes: &[u8]| TableFile::new(name, bytes).open();
+
+	assert!(matches!(open("short", &good[..10]), Err(SnapshotError::Truncated)));
+	assert!(matches!(open("data", &good[..good.len() - 1]), Err(SnapshotError::Truncated)));
+
+	let mut magic = good.clone();
+	magic[0] = b'x';
+	assert!(matches!(open("magic", &magic), Err(SnapshotError::BadMagic)));
+
+	let mut version = good.clone();
+	version[8] = 9;
+	assert!(matches!(open("version", &version), Err(SnapshotError::UnsupportedVersion(9))));
+
+	let mut utf8 = good.clone();
+	*utf8.last_mut().unwrap() = 0xff;
+	assert!(matches!(open("utf8", &utf8), Err(SnapshotError::InvalidUtf8 { index: 1 })));
+
+	// valid UTF-8, but doesn't match the stored hash anymore
+	let mut hash = good.clone();
+	*hash.last_mut().unwrap() = b'x';
+	assert!(matches!(open("hash", &hash), Err(SnapshotError::Malformed)));
+
+	// table with every slot pointing at an entry
+	let mut full = good.clone();
+	let table_offset = read_usize(&good, 40).unwrap();
+	full[table_offset..table_offset + 4 * 4].fill(0);
+	assert!(matches!(open("full", &full), Err(SnapshotError::Malformed)));
+
+	let mut extra = good.clone();
+	extra.push(b'x');
+	assert!(matches!(open("extra", &extra), Err(SnapshotError::Malformed)));
+}
+
+#[test]
+fn open_unchecked() {
+	let good = table(&["one", "two"]);
+	let pool = TableFile::new("unchecked", &good).open_unchecked().unwrap();
+	assert_eq!(pool.len(), 2);
+	assert_eq!(pool.get("two").unwrap(), "two");
+	assert!(pool.get("three").is_none());
+
+	// the header is still checked
+	let open = |name: &str, bytes: &[u8]| TableFile::new(name, bytes).open_unchecked();
+	assert!(matches!(open("unchecked-short", &good[..10]), Err(SnapshotError::Truncated)));
+	assert!(matches!(open("unchecked-data", &good[..good.len() - 1]), Err(SnapshotError::Truncated)));
+
+	// but entries aren't, so a wrong hash only shows up as the entry missing
+	let mut hash = good.clone();
+	*hash.last_mut().unwrap() = b'x';
+	let pool = open("unchecked-hash", &hash).unwrap();
+	assert!(pool.get("twx").is_none());
+	assert_eq!(pool.get("one").unwrap(), "one");
+}
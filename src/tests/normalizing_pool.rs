@@ -0,0 +1,50 @@
+use super::*;
+use crate::String;
+use crate::pool::ArenaPool;
+
+crate::pool_conformance_tests!(conformance_nfc, NormalizingPool::nfc(ArenaPool::new()), canonicalizing);
+crate::pool_conformance_tests!(conformance_nfkc, NormalizingPool::nfkc(ArenaPool::new()), canonicalizing);
+
+const COMPOSED: &str = "caf\u{e9}";
+const DECOMPOSED: &str = "cafe\u{301}";
+
+#[test]
+fn nfc() {
+	let pool = NormalizingPool::nfc(ArenaPool::new());
+
+	let a = String::from_str_in(COMPOSED, pool.clone());
+	let b = String::from_str_in(DECOMPOSED, pool.clone());
+
+	assert_eq!(a, COMPOSED);
+	assert_eq!(a, b);
+	assert_eq!(pool.inner().len(), 1);
+
+	// compatibility characters are left alone
+	assert_eq!(String::from_str_in("\u{fb01}", pool.clone()), "\u{fb01}");
+}
+
+#[test]
+fn nfkc() {
+	let pool = NormalizingPool::nfkc(ArenaPool::new());
+
+	assert_eq!(String::from_str_in(DECOMPOSED, pool.clone()), COMPOSED);
+	assert_eq!(String::from_str_in("\u{fb01}", pool.clone()), "fi");
+}
+
+#[test]
+fn fast_paths_borrow() {
+	let nfc = UnicodeNormalizer::Nfc;
+
+	assert!(matches!(nfc.canonicalize("ascii"), Cow::Borrowed(_)));
+	assert!(matches!(nfc.canonicalize(COMPOSED), Cow::Borrowed(_)));
+	assert!(matches!(nfc.canonicalize(DECOMPOSED), Cow::Owned(_)));
+}
+
+#[test]
+fn keeps_original() {
+	let pool = NormalizingPool::with_original(ArenaPool::new(), UnicodeNormalizer::Nfc);
+	let s = String::from_str_in(DECOMPOSED, pool);
+
+	assert_eq!(s, COMPOSED);
+	assert_eq!(s.original(), DECOMPOSED);
+}
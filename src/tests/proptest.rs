@@ -0,0 +1,230 @@
+use super::*;
+use crate::pool::{ ArenaPool, GlobalPool };
+use ::std::collections::hash_map::DefaultHasher;
+use ::std::hash::{ Hash, Hasher };
+
+/// An operation on a string, applied to both a pooled and a std string
+#[derive(Clone, Debug)]
+enum Op {
+	PushStr(StdString),
+	Push(char),
+	Truncate(usize),
+	Pop,
+	Remove(usize),
+	Retain(u64),
+	Insert(usize, char),
+	InsertStr(usize, StdString),
+	SplitOff(usize),
+	Clear,
+	AddStr(StdString),
+	AddAssignString(StdString)
+}
+
+fn op() -> impl Strategy<Value = Op> {
+	prop_oneof![
+		std_string().prop_map(Op::PushStr),
+		any::<char>().prop_map(Op::Push),
+		any::<usize>().prop_map(Op::Truncate),
+		Just(Op::Pop),
+		any::<usize>().prop_map(Op::Remove),
+		any::<u64>().prop_map(Op::Retain),
+		(any::<usize>(), any::<char>()).prop_map(|(i, ch)| Op::Insert(i, ch)),
+		(any::<usize>(), std_string()).prop_map(|(i, s)| Op::InsertStr(i, s)),
+		any::<usize>().prop_map(Op::SplitOff),
+		Just(Op::Clear),
+		std_string().prop_map(Op::AddStr),
+		std_string().prop_map(Op::AddAssignString)
+	]
+}
+
+/// Turns an arbitrary index into a char boundary in `s` (including `s.len()`)
+fn boundary(s: &str, i: usize) -> usize {
+	let mut i = i % (s.len() + 1);
+	while !s.is_char_boundary(i) { i -= 1 }
+	i
+}
+
+/// Applies `ops` to a pooled string and a std string, checking they match
+/// after every one
+fn check_ops<P: Pool>(initial: &str, ops: &[Op], pool: P) -> Result<(), TestCaseError> {
+	let mut pooled = String::from_str_in(initial, pool.clone());
+	let mut std = StdString::from(initial);
+
+	for op in ops {
+		match op.clone() {
+			Op::PushStr(s) => {
+				pooled.push_str(&s);
+				std.push_str(&s);
+			}
+			Op::Push(ch) => {
+				pooled.push(ch);
+				std.push(ch);
+			}
+			Op::Truncate(i) => {
+				// past the end too, which does nothing
+				let i = if i % 4 == 0 { std.len() + i % 8 } else { boundary(&std, i) };
+				pooled.truncate(i);
+				std.truncate(i);
+			}
+			Op::Pop => {
+				prop_assert_eq!(pooled.pop(), std.pop());
+			}
+			Op::Remove(i) => {
+				if std.is_empty() { continue }
+				let i = match boundary(&std, i) {
+					i if i == std.len() => { std.char_indices().last().unwrap().0 }
+					i => { i }
+				};
+				prop_assert_eq!(pooled.remove(i), std.remove(i));
+			}
+			Op::Retain(mask) => {
+				let keep = |ch: char| mask & (1 << (ch as u32 % 64)) != 0;
+				pooled.retain(keep);
+				std.retain(keep);
+			}
+			Op::Insert(i, ch) => {
+				let i = boundary(&std, i);
+				pooled.insert(i, ch);
+				std.insert(i, ch);
+			}
+			Op::InsertStr(i, s) => {
+				let i = boundary(&std, i);
+				pooled.insert_str(i, &s);
+				std.insert_str(i, &s);
+			}
+			Op::SplitOff(i) => {
+				let i = boundary(&std, i);
+				let pooled_rest = pooled.split_off(i);
+				let std_rest = std.split_off(i);
+				prop_assert_eq!(pooled_rest.as_str(), std_rest.as_str());
+
+				let pooled_rest = pooled.split_off_in(pooled.len(), GlobalPool);
+				prop_assert_eq!(pooled_rest.as_str(), "");
+			}
+			Op::Clear => {
+				pooled.clear();
+				std.clear();
+			}
+			Op::AddStr(s) => {
+				prop_assert_eq!((&pooled + s.as_str()).to_string(), std.clone() + &s);
+				// testing Add for owned strings, not AddAssign
+				#[allow(clippy::assign_op_pattern)]
+				{ pooled = pooled + s.as_str(); }
+				std += &s;
+			}
+			Op::AddAssignString(s) => {
+				pooled += String::from_str_in(&s, pool.clone());
+				std += &s;
+			}
+		}
+
+		prop_assert_eq!(pooled.as_str(), std.as_str());
+		prop_assert_eq!(pooled.as_bytes(), std.as_bytes());
+		prop_assert_eq!(pooled.len(), std.len());
+	}
+
+	prop_assert_eq!(pooled.clone().into_bytes(), std.clone().into_bytes());
+	prop_assert_eq!(pooled.clone().into_boxed_str(), std.clone().into_boxed_str());
+	prop_assert_eq!(&*pooled.leak(), std.as_str());
+	Ok(())
+}
+
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	value.hash(&mut hasher);
+	hasher.finish()
+}
+
+/// Edge case for `remove`: only multi-byte chars, removing the first one
+#[test]
+fn remove_leading_multibyte_char() {
+	let ops = [Op::Remove(0)];
+	check_ops("Ω≈ç√", &ops, GlobalPool).unwrap();
+	check_ops("Ω≈ç√", &ops, ArenaPool::new()).unwrap();
+}
+
+proptest! {
+	// failures found are added as unit tests (like the one above) instead of
+	// persisted into a regressions file, which would end up in the package
+	#![proptest_config(ProptestConfig { failure_persistence: None, ..ProptestConfig::default() })]
+
+	#[test]
+	fn ops_match_std(initial in std_string(), ops in prop::collection::vec(op(), 0..16)) {
+		check_ops(&initial, &ops, GlobalPool)?;
+		check_ops(&initial, &ops, ArenaPool::new())?;
+	}
+
+	#[test]
+	fn constructors_match_std(bytes in prop::collection::vec(any::<u8>(), 0..32), s in std_string()) {
+		let utf16 = s.encode_utf16().collect::<Vec<_>>();
+
+		prop_assert_eq!(String::from_utf8(bytes.clone()).ok().map(|s| s.to_string()), StdString::from_utf8(bytes.clone()).ok());
+		prop_assert_eq!(String::from_utf8_slice(&bytes).ok().map(|s| s.to_string()), std::str::from_utf8(&bytes).ok().map(StdString::from));
+		prop_assert_eq!(String::from_utf8_lossy(&bytes), &*StdString::from_utf8_lossy(&bytes));
+		prop_assert_eq!(String::from_utf8(s.clone().into_bytes()).unwrap(), s.as_str());
+		prop_assert_eq!(String::from_utf16(&utf16).unwrap(), s.as_str());
+		prop_assert_eq!(String::from_utf16_lossy(&utf16), s.as_str());
+
+		prop_assert_eq!(unsafe { String::from_utf8_unchecked(s.clone().into_bytes()) }, s.as_str());
+		prop_assert_eq!(unsafe { String::from_utf8_unchecked_slice(s.as_bytes()) }, s.as_str());
+
+		let pool = ArenaPool::new();
+		prop_assert_eq!(String::new_in(pool.clone()).to_string(), StdString::new());
+		prop_assert_eq!(String::from_utf8_in(bytes.clone(), pool.clone()).ok().map(|s| s.to_string()), StdString::from_utf8(bytes.clone()).ok());
+		prop_assert_eq!(String::from_utf8_slice_in(&bytes, pool.clone()).ok().map(|s| s.to_string()), std::str::from_utf8(&bytes).ok().map(StdString::from));
+		prop_assert_eq!(unsafe { String::from_utf8_unchecked_in(s.clone().into_bytes(), pool.clone()) }, s.as_str());
+		prop_assert_eq!(unsafe { String::from_utf8_unchecked_slice_in(s.as_bytes(), pool.clone()) }, s.as_str());
+		prop_assert_eq!(String::from_utf8_lossy_in(&bytes, pool.clone()), &*StdString::from_utf8_lossy(&bytes));
+		prop_assert_eq!(String::from_utf16_in(&utf16, pool.clone()).unwrap(), s.as_str());
+		prop_assert_eq!(String::from_utf16_lossy_in(&utf16, pool.clone()), s.as_str());
+		prop_assert_eq!(String::from_str_in(&s, pool), s.as_str());
+	}
+
+	#[test]
+	fn traits_match_std(a in any::<String>(), b in string_in(ArenaPool::new())) {
+		let std_a = StdString::from(a.as_str());
+		let std_b = StdString::from(b.as_str());
+
+		prop_assert_eq!(a == b, std_a == std_b);
+		prop_assert_eq!(hash(&a), hash(&std_a));
+		prop_assert_eq!(hash(&b), hash(&std_b));
+		prop_assert_eq!(hash(&a), hash(a.as_str()));
+		prop_assert_eq!(a.to_string(), std_a.to_string());
+		prop_assert_eq!(format!("{a:?}").contains(&format!("{std_a:?}")), true);
+
+		prop_assert_eq!((a.clone() + &b).to_string(), std_a.clone() + &std_b);
+		prop_assert_eq!((&a + b.clone()).to_string(), std_a.clone() + &std_b);
+		prop_assert_eq!(a.to_other_pool(ArenaPool::new()), a.clone());
+		prop_assert_eq!(b.clone().into_other_pool(GlobalPool), b.clone());
+	}
+
+	#[test]
+	fn clone_to_matches(a in any::<String>(), b in string_in(ArenaPool::new())) {
+		let pool = ArenaPool::new();
+		let a_arena = a.clone_to(pool.clone());
+		let b_global = b.clone_to(GlobalPool);
+		let b_arena = b.clone_to(pool.clone());
+
+		prop_assert_eq!(a_arena.as_str(), a.as_str());
+		prop_assert_eq!(b_global.as_str(), b.as_str());
+		prop_assert_eq!(&a_arena, &a);
+		prop_assert_eq!(&b_global, &b);
+		prop_assert_eq!(a_arena == b_arena, a == b);
+		prop_assert_eq!(a == b_global, a.as_str() == b.as_str());
+	}
+
+	#[test]
+	fn equal_strings_share_allocation(strings in prop::collection::vec(any::<String>(), 0..32)) {
+		for a in &strings {
+			for b in &strings {
+				prop_assert_eq!(a == b, a.as_ptr() == b.as_ptr());
+			}
+		}
+	}
+
+	#[test]
+	fn samples_are_duplicated(strings in prop::collection::vec(std_string(), 64)) {
+		let distinct = strings.iter().collect::<::std::collections::HashSet<_>>();
+		prop_assert!(distinct.len() < strings.len());
+	}
+}
@@ -0,0 +1,62 @@
+use super::*;
+use crate::pool::{ ArenaPool, GlobalPool };
+use ::rkyv::{ AlignedVec, Infallible };
+use ::rkyv::ser::serializers::AllocSerializer;
+use ::std::sync::Arc;
+
+fn to_bytes<T: Serialize<AllocSerializer<256>>>(value: &T) -> AlignedVec {
+	::rkyv::to_bytes::<_, 256>(value).unwrap()
+}
+
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct Doc {
+	title: String,
+	tags: Vec<String>
+}
+
+#[test]
+fn roundtrip() {
+	let doc = Doc {
+		title: String::from("rkyv roundtrip"),
+		tags: ["a", "b", "a", ""].map(String::from).to_vec()
+	};
+
+	let bytes = to_bytes(&doc);
+	let archived = ::rkyv::check_archived_root::<Doc>(&bytes).unwrap();
+	assert_eq!(archived.title.get(), "rkyv roundtrip");
+	assert_eq!(archived.tags.iter().map(|t| t.get()).collect::<Vec<_>>(), ["a", "b", "a", ""]);
+
+	let back: Doc = archived.deserialize(&mut Infallible).unwrap();
+	assert!(Arc::ptr_eq(back.title.raw(), doc.title.raw()));
+	assert!(Arc::ptr_eq(back.tags[0].raw(), back.tags[2].raw()));
+	assert_eq!(back.tags[3], "");
+}
+
+#[test]
+fn shared_allocation_is_archived_once() {
+	let long = "rkyv dedup ".repeat(100);
+	let strings = vec![String::from(&*long); 100];
+	let bytes = to_bytes(&strings);
+	assert!(bytes.len() < 2 * long.len(), "archive is {} bytes", bytes.len());
+
+	let archived = unsafe { ::rkyv::archived_root::<Vec<String>>(&bytes) };
+	assert!(archived.iter().all(|s| std::ptr::eq(s.get(), archived[0].get())));
+}
+
+#[test]
+fn intern_in() {
+	let source = ArenaPool::new();
+	let strings = ["x", "", "y", "x"].map(|s| String::from_str_in(s, source.clone())).to_vec();
+	let bytes = to_bytes(&strings);
+	let archived = unsafe { ::rkyv::archived_root::<Vec<String<ArenaPool>>>(&bytes) };
+
+	let target = ArenaPool::new();
+	let back: Vec<String<ArenaPool>> = archived.deserialize(&mut InternIn(target.clone())).unwrap();
+	assert_eq!(back, strings);
+	assert_eq!(back[0].as_str().as_ptr(), back[3].as_str().as_ptr());
+	assert_eq!(target.len(), 3);
+
+	let global: Vec<String<GlobalPool>> = archived.deserialize(&mut Infallible).unwrap();
+	assert_eq!(global, strings);
+}
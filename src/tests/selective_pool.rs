@@ -0,0 +1,95 @@
+use super::*;
+use crate::String;
+use crate::pool::ArenaPool;
+
+crate::pool_conformance_tests!(conformance_max_len, SelectivePool::new(ArenaPool::new(), MaxLen(8)));
+crate::pool_conformance_tests!(conformance_frequency, SelectivePool::new(ArenaPool::new(), FrequencyThreshold::new(2)));
+
+#[test]
+fn max_len() {
+	let pool = SelectivePool::new(ArenaPool::new(), MaxLen(5));
+
+	let short = String::from_str_in("short", pool.clone());
+	let long = String::from_str_in("longer", pool.clone());
+
+	assert!(short.raw().is_pooled());
+	assert!(!long.raw().is_pooled());
+	assert_eq!(long, "longer");
+	assert_eq!(pool.inner().len(), 1);
+
+	// still behaves like any other string
+	let mut s = short.clone();
+	s.push('!');
+	assert!(!s.raw().is_pooled());
+	s.pop();
+	assert!(s.raw().is_pooled());
+	assert_eq!(s, short);
+}
+
+#[test]
+fn predicate() {
+	let policy = Predicate::new(|slices: &SlicesWrap| slices.into_iter().all(|b| b.is_ascii_lowercase()));
+	let pool = SelectivePool::new(ArenaPool::new(), policy);
+
+	assert!(String::from_str_in("ident", pool.clone()).raw().is_pooled());
+	assert!(!String::from_str_in("Not An Ident", pool.clone()).raw().is_pooled());
+}
+
+#[test]
+fn frequency_threshold() {
+	let pool = SelectivePool::new(ArenaPool::new(), FrequencyThreshold::new(3));
+
+	let first = String::from_str_in("frequent", pool.clone());
+	let second = String::from_str_in("frequent", pool.clone());
+	let third = String::from_str_in("frequent", pool.clone());
+	let fourth = String::from_str_in("frequent", pool.clone());
+
+	assert!(!first.raw().is_pooled());
+	assert!(!second.raw().is_pooled());
+	assert!(third.raw().is_pooled());
+	assert!(fourth.raw().is_pooled());
+	assert_eq!(first, fourth);
+	assert_eq!(pool.inner().len(), 1);
+
+	assert!(!String::from_str_in("rare", pool.clone()).raw().is_pooled());
+}
+
+#[test]
+fn frequency_threshold_ages() {
+	let policy = FrequencyThreshold::new(2).with_window(8);
+	assert_eq!(policy.window(), 8);
+	assert!(format!("{policy:?}").contains("window: 8"));
+	let pool = SelectivePool::new(ArenaPool::new(), policy);
+
+	// seen once per window, so counted twice at most before being halved
+	for window in 0..100 {
+		assert!(!String::from_str_in("once per window", pool.clone()).raw().is_pooled());
+		for i in 0..7 {
+			String::from_str_in(&format!("filler {window} {i}"), pool.clone());
+		}
+	}
+
+	// seen twice within a window still gets interned
+	String::from_str_in("twice", pool.clone());
+	assert!(String::from_str_in("twice", pool.clone()).raw().is_pooled());
+}
+
+#[test]
+fn rejected_but_already_interned_is_shared() {
+	let pool = SelectivePool::new(ArenaPool::new(), FrequencyThreshold::new(2).with_window(4));
+
+	String::from_str_in("interned", pool.clone());
+	let interned = String::from_str_in("interned", pool.clone());
+	assert!(interned.raw().is_pooled());
+
+	// halves the counters twice, so "interned" is back down to 0
+	for i in 0..6 {
+		String::from_str_in(&format!("filler {i}"), pool.clone());
+	}
+
+	// the policy says no now, but it's still in the pool
+	let again = String::from_str_in("interned", pool.clone());
+	assert!(again.raw().is_pooled());
+	assert_eq!(again.as_str().as_ptr(), interned.as_str().as_ptr());
+	assert!(!String::from_str_in("filler 0", pool.clone()).raw().is_pooled());
+}
@@ -0,0 +1,59 @@
+use super::*;
+use crate::pool::{ ArenaPool, FrozenPool, GlobalPool, SlicesWrap };
+use ::std::sync::Arc;
+
+#[test]
+fn roundtrip() {
+	let s = String::from("serde roundtrip");
+	let json = serde_json::to_string(&s).unwrap();
+	assert_eq!(json, r#""serde roundtrip""#);
+
+	let back: String = serde_json::from_str(&json).unwrap();
+	assert!(Arc::ptr_eq(back.raw(), s.raw()));
+
+	let escaped: String = serde_json::from_str(r#""serde \"escaped\"""#).unwrap();
+	assert_eq!(escaped, r#"serde "escaped""#);
+
+	let from_reader: String = serde_json::from_reader(json.as_bytes()).unwrap();
+	assert!(Arc::ptr_eq(from_reader.raw(), s.raw()));
+}
+
+#[test]
+fn in_struct() {
+	#[derive(::serde::Serialize, ::serde::Deserialize)]
+	struct Row {
+		name: String<ArenaPool>,
+		tags: Vec<String>
+	}
+
+	let json = r#"{"name":"row","tags":["serde tag","serde tag"]}"#;
+	let row: Row = serde_json::from_str(json).unwrap();
+	assert_eq!(row.name, "row");
+	assert!(Arc::ptr_eq(row.tags[0].raw(), row.tags[1].raw()));
+	assert_eq!(serde_json::to_string(&row).unwrap(), json);
+}
+
+#[test]
+fn intern_in() {
+	let pool = FrozenPool::new(["known"]);
+
+	let known = InternIn(pool.clone())
+		.deserialize(&mut serde_json::Deserializer::from_str(r#""known""#))
+		.unwrap();
+	assert!(Arc::ptr_eq(known.raw(), &pool.raw_get(SlicesWrap(&[b"known"])).unwrap()));
+
+	let err = InternIn(GlobalPool)
+		.deserialize(&mut serde_json::Deserializer::from_str("1"))
+		.unwrap_err();
+	assert!(err.to_string().contains("expected a string"));
+}
+
+#[test]
+fn bytes() {
+	let visitor = StringVisitor(GlobalPool);
+	let s = visitor.visit_bytes::<serde_json::Error>(b"serde bytes").unwrap();
+	assert_eq!(s, "serde bytes");
+
+	let visitor = StringVisitor(GlobalPool);
+	assert!(visitor.visit_bytes::<serde_json::Error>(b"\xff").is_err());
+}
@@ -0,0 +1,221 @@
+use super::*;
+use crate::String;
+use ::std::mem;
+use ::std::panic::{ self, AssertUnwindSafe };
+use ::std::sync::atomic::AtomicUsize;
+use ::std::thread;
+
+crate::pool_conformance_tests!(conformance, conformance_pool());
+
+/// Creates a segment with a name unique to this test, unlinking it when dropped
+struct TestSegment(std::string::String);
+
+impl TestSegment {
+	fn create(max_entries: usize, max_bytes: usize) -> (Self, ShmPool) {
+		static COUNTER: AtomicUsize = AtomicUsize::new(0);
+		let name = format!("/string-pool-test-{}-{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed));
+
+		let pool = ShmPool::create(&name, max_entries, max_bytes).unwrap();
+		(Self(name), pool)
+	}
+
+	fn open(&self) -> ShmPool {
+		ShmPool::open(&self.0).unwrap()
+	}
+}
+
+impl Drop for TestSegment {
+	fn drop(&mut self) {
+		let _ = ShmPool::unlink(&self.0);
+	}
+}
+
+/// Creates a small segment, which is unlinked again straight away, but stays
+/// mapped
+fn conformance_pool() -> ShmPool {
+	TestSegment::create(64, 4096).1
+}
+
+/// Runs `f` in a forked child process, returning its exit code (1 if `f`
+/// panicked)
+fn in_child(f: impl FnOnce()) -> i32 {
+	match unsafe { libc::fork() } {
+		-1 => { panic!("fork failed: {}", io::Error::last_os_error()) }
+		0 => {
+			let code = match panic::catch_unwind(AssertUnwindSafe(f)) {
+				Ok(()) => { 0 }
+				Err(_) => { 1 }
+			};
+			unsafe { libc::_exit(code) }
+		}
+		pid => {
+			let mut status = 0;
+			assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+			assert!(libc::WIFEXITED(status));
+			libc::WEXITSTATUS(status)
+		}
+	}
+}
+
+fn offset(s: &String<ShmPool>) -> u64 {
+	match s.raw() {
+		PooledOrOwned::Pooled(raw) => { raw.offset() }
+		PooledOrOwned::Owned(_) => { panic!("string isn't in the segment") }
+	}
+}
+
+#[test]
+fn intern() {
+	let (_segment, pool) = TestSegment::create(16, 1024);
+
+	let a = String::from_str_in("shared", pool.clone());
+	let b = String::from_str_in("shared", pool.clone());
+	let c = String::from_str_in("other", pool.clone());
+	assert_eq!(a, "shared");
+	assert_eq!(offset(&a), offset(&b));
+	assert_ne!(offset(&a), offset(&c));
+	assert_eq!(a.as_str().as_ptr(), b.as_str().as_ptr());
+	assert_eq!(a.precomputed_hash(), b.precomputed_hash());
+
+	assert_eq!(pool.len(), 2);
+	assert!(pool.get("shared").is_some());
+	assert!(pool.get("missing").is_none());
+	assert_eq!(pool.len(), 2);
+}
+
+#[test]
+fn separate_mappings() {
+	let (segment, first) = TestSegment::create(16, 1024);
+	let second = segment.open();
+
+	let a = String::from_str_in("mapped twice", first.clone());
+	let b = second.get("mapped twice").unwrap();
+	let c = String::from_str_in("mapped twice", second.clone());
+
+	// same offset, but mapped at different addresses
+	assert_eq!(offset(&a), offset(&b));
+	assert_eq!(offset(&a), offset(&c));
+	assert_ne!(a.as_str().as_ptr(), b.as_str().as_ptr());
+	assert_eq!(second.len(), 1);
+
+	// strings stay valid after the pool handles go away
+	drop(first);
+	drop(second);
+	assert_eq!(a, "mapped twice");
+}
+
+#[test]
+fn full() {
+	let (_segment, pool) = TestSegment::create(2, 64);
+
+	let a = String::from_str_in("a", pool.clone());
+	let b = String::from_str_in("b", pool.clone());
+	let c = String::from_str_in("c", pool.clone());
+	assert!(a.raw().is_pooled());
+	assert!(b.raw().is_pooled());
+	assert!(!c.raw().is_pooled());
+	assert_eq!(c, "c");
+
+	let (_segment, pool) = TestSegment::create(16, 16);
+	let long = String::from_str_in(&"x".repeat(1000), pool.clone());
+	assert!(!long.raw().is_pooled());
+	assert!(pool.is_empty());
+}
+
+#[test]
+fn concurrent() {
+	let (segment, pool) = TestSegment::create(1024, 64 * 1024);
+
+	let offsets = thread::scope(|scope| {
+		let handles = (0..8)
+			.map(|t| {
+				// half of the threads use their own mapping
+				let pool = if t % 2 == 0 { pool.clone() } else { segment.open() };
+				scope.spawn(move || {
+					(0..500)
+						.map(|i| offset(&String::from_str_in(&format!("concurrent {i}"), pool.clone())))
+						.collect::<Vec<_>>()
+				})
+			})
+			.collect::<Vec<_>>();
+		handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+	});
+
+	for other in &offsets[1..] {
+		assert_eq!(*other, offsets[0]);
+	}
+	assert_eq!(pool.len(), 500);
+}
+
+#[test]
+fn open_errors() {
+	assert!(ShmPool::open("/string-pool-test-does-not-exist").is_err());
+
+	let (segment, _pool) = TestSegment::create(1, 1);
+	assert!(ShmPool::create(&segment.0, 1, 1).is_err());
+}
+
+#[test]
+fn other_process() {
+	let (segment, pool) = TestSegment::create(16, 1024);
+	let parent = String::from_str_in("from parent", pool.clone());
+
+	let code = in_child(|| {
+		let pool = segment.open();
+		let parent = pool.get("from parent").unwrap();
+		assert_eq!(parent, "from parent");
+		String::from_str_in("from child", pool.clone());
+	});
+	assert_eq!(code, 0);
+
+	let child = pool.get("from child").unwrap();
+	assert_eq!(child, "from child");
+	assert_ne!(offset(&child), offset(&parent));
+	assert_eq!(pool.len(), 2);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn lock_holder_died() {
+	let (segment, pool) = TestSegment::create(16, 1024);
+
+	// dies while holding the lock, without unlocking it. The segment has to
+	// stay mapped until the process exits, for the kernel to mark the lock
+	// as abandoned, like it would when crashing
+	let code = in_child(|| {
+		let pool = segment.open();
+		mem::forget(pool.segment.lock());
+		mem::forget(pool);
+	});
+	assert_eq!(code, 0);
+
+	// would block forever if the lock wasn't recovered
+	let a = String::from_str_in("after crash", pool.clone());
+	let b = String::from_str_in("after crash, again", pool.clone());
+	assert!(a.raw().is_pooled());
+	assert!(b.raw().is_pooled());
+	assert_eq!(segment.open().get("after crash").unwrap(), "after crash");
+}
+
+#[test]
+fn open_bad_header() {
+	let (segment, pool) = TestSegment::create(16, 1024);
+	let header = pool.segment.ptr as *mut Header;
+	let table_len = pool.segment.header().table_len;
+
+	let open_with = |value: u64| {
+		unsafe { ptr::addr_of_mut!((*header).table_len).write(value) }
+		ShmPool::open(&segment.0).unwrap_err().kind()
+	};
+
+	// not a power of two, overflowing, and too big for the segment
+	assert_eq!(open_with(table_len + 1), io::ErrorKind::InvalidData);
+	assert_eq!(open_with(1 << 62), io::ErrorKind::InvalidData);
+	assert_eq!(open_with(table_len * 2), io::ErrorKind::InvalidData);
+
+	unsafe { ptr::addr_of_mut!((*header).table_len).write(table_len) }
+	assert!(ShmPool::open(&segment.0).is_ok());
+
+	pool.segment.header().magic.store(0, Ordering::Relaxed);
+	assert_eq!(ShmPool::open(&segment.0).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,146 @@
+use super::*;
+use crate::String;
+use crate::pool::{ ArenaPool, FrozenPool, GlobalPool, Pool, SlicesWrap };
+
+fn snapshot_of(entries: &[&[u8]]) -> Vec<u8> {
+	let mut buf = Vec::new();
+	write(&mut buf, entries.iter().copied()).unwrap();
+	buf
+}
+
+fn read_all(bytes: &[u8]) -> Result<Vec<std::string::String>, SnapshotError> {
+	let mut entries = Vec::new();
+	read(bytes, |s| entries.push(s.to_owned()))?;
+	Ok(entries)
+}
+
+/// Replaces the checksum at the end of `buf` with the right one
+fn fix_checksum(buf: &mut Vec<u8>) {
+	buf.truncate(buf.len() - 8);
+	let mut fnv = Fnv::new();
+	fnv.write(buf);
+	buf.extend_from_slice(&fnv.finish().to_le_bytes());
+}
+
+#[test]
+fn roundtrip() {
+	let long = "long ".repeat(100);
+	let entries = ["", "a", "snapshot", &*long, "ünïcödé"];
+	let buf = snapshot_of(&entries.map(str::as_bytes));
+
+	assert_eq!(read_all(&buf).unwrap(), entries);
+	assert_eq!(read_all(&snapshot_of(&[])).unwrap(), Vec::<std::string::String>::new());
+}
+
+#[test]
+fn varints() {
+	for value in [0, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+		let mut buf = Vec::new();
+		write_varint(&mut buf, value).unwrap();
+
+		let mut bytes = &*buf;
+		assert_eq!(read_varint(&mut bytes).unwrap(), value);
+		assert!(bytes.is_empty());
+	}
+}
+
+#[test]
+fn overlong_varints() {
+	// 11 bytes, the 10th one still saying there's more
+	let mut bytes = &[0xff; 11][..];
+	assert!(matches!(read_varint(&mut bytes), Err(SnapshotError::Malformed)));
+
+	// 10 bytes, but the last one has more than the 64th bit set
+	let mut buf = [0xff; 10];
+	buf[9] = 0x02;
+	assert!(matches!(read_varint(&mut &buf[..]), Err(SnapshotError::Malformed)));
+
+	// u64::MAX is the most that fits
+	buf[9] = 0x01;
+	assert_eq!(read_varint(&mut &buf[..]).unwrap(), u64::MAX);
+}
+
+#[test]
+fn corrupt() {
+	let buf = snapshot_of(&[b"one", b"two"]);
+
+	assert!(matches!(read_all(b"str"), Err(SnapshotError::Truncated)));
+	assert!(matches!(read_all(b"not a snapshot at all"), Err(SnapshotError::BadMagic)));
+	assert!(matches!(read_all(&buf[..10]), Err(SnapshotError::Truncated)));
+	assert!(matches!(read_all(&buf[..buf.len() - 1]), Err(SnapshotError::ChecksumMismatch)));
+
+	let mut flipped = buf.clone();
+	flipped[14] ^= 1;
+	assert!(matches!(read_all(&flipped), Err(SnapshotError::ChecksumMismatch)));
+
+	let mut version = buf.clone();
+	version[8] = 2;
+	assert!(matches!(read_all(&version), Err(SnapshotError::UnsupportedVersion(2))));
+
+	let mut invalid = snapshot_of(&[b"ok", b"\xff\xfe"]);
+	fix_checksum(&mut invalid);
+	assert!(matches!(read_all(&invalid), Err(SnapshotError::InvalidUtf8 { index: 1 })));
+
+	// count says 3, only 2 entries
+	let mut count = buf.clone();
+	count[12] = 3;
+	fix_checksum(&mut count);
+	assert!(matches!(read_all(&count), Err(SnapshotError::Malformed)));
+
+	// count says 1, leftover entry
+	let mut count = buf.clone();
+	count[12] = 1;
+	fix_checksum(&mut count);
+	assert!(matches!(read_all(&count), Err(SnapshotError::Malformed)));
+}
+
+#[test]
+fn corrupt_does_not_load() {
+	let pool = ArenaPool::new();
+	let mut buf = snapshot_of(&[b"corrupt does not load", b"\xff"]);
+	fix_checksum(&mut buf);
+
+	assert!(pool.load_snapshot(&*buf).is_err());
+	assert!(pool.is_empty());
+}
+
+#[test]
+fn global_pool() {
+	let s = String::from("global pool snapshot");
+	let mut buf = Vec::new();
+	GlobalPool.save_snapshot(&mut buf).unwrap();
+
+	let loaded = read_all(&buf).unwrap();
+	assert!(loaded.iter().any(|l| l == "global pool snapshot"));
+
+	let mut other = Vec::new();
+	write(&mut other, [&b"global pool loaded snapshot"[..]].into_iter()).unwrap();
+	assert_eq!(GlobalPool.load_snapshot(&*other).unwrap(), 1);
+	assert!(GlobalPool.get("global pool loaded snapshot").is_some());
+}
+
+#[test]
+fn arena_pool() {
+	let pool = ArenaPool::new();
+	let strings = ["a", "b", "c"].map(|s| String::from_str_in(s, pool.clone()));
+
+	let mut buf = Vec::new();
+	pool.save_snapshot(&mut buf).unwrap();
+
+	let restored = ArenaPool::new();
+	assert_eq!(restored.load_snapshot(&*buf).unwrap(), 3);
+	assert_eq!(restored.len(), 3);
+	assert!(restored.raw_get(SlicesWrap(&[b"b"])).is_some());
+}
+
+#[test]
+fn frozen_pool() {
+	let pool = FrozenPool::new(["x", "y"]);
+	let mut buf = Vec::new();
+	pool.save_snapshot(&mut buf).unwrap();
+
+	let restored = FrozenPool::load_snapshot(&*buf).unwrap();
+	assert_eq!(restored.len(), 2);
+	assert!(restored.get("x").is_some());
+	assert!(restored.get("z").is_none());
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::{ GlobalPool, String };
+use crate::pool::ArenaPool;
+use ::hashbrown::hash_map::DefaultHashBuilder;
+use ::std::hash::BuildHasher;
+
+crate::pool_conformance_tests!(conformance, SsoPool::<_>::new(GlobalPool));
+crate::pool_conformance_tests!(conformance_small, SsoPool::<_, 4>::new(ArenaPool::new()));
+
+#[test]
+fn inline_up_to_n() {
+	let pool = SsoPool::<_, 4>::new(GlobalPool);
+
+	let empty = String::new_in(pool.clone());
+	assert!(empty.raw().is_inline());
+	assert_eq!(empty, "");
+
+	let short = String::from_str_in("abcd", pool.clone());
+	assert!(short.raw().is_inline());
+	assert_eq!(short, "abcd");
+
+	let long = String::from_str_in("abcde", pool.clone());
+	assert!(!long.raw().is_inline());
+	assert_eq!(long, "abcde");
+}
+
+#[test]
+fn representation_follows_length() {
+	let pool = SsoPool::<_, 4>::new(GlobalPool);
+	let mut s = String::new_in(pool);
+
+	for (i, ch) in "🫐🫐🫐".chars().enumerate() {
+		s.push(ch);
+		assert_eq!(s.raw().is_inline(), s.len() <= 4, "iteration {i}");
+	}
+
+	while s.pop().is_some() {
+		assert_eq!(s.raw().is_inline(), s.len() <= 4);
+	}
+	assert_eq!(s, "");
+}
+
+#[test]
+fn eq_and_hash_match_pooled() {
+	let hash_builder = DefaultHashBuilder::default();
+	let pool = SsoPool::<_>::new(GlobalPool);
+
+	for s in ["", "short", "a string that is a fair bit longer than 22 bytes"] {
+		let sso = String::from_str_in(s, pool.clone());
+		let global = String::from(s);
+
+		assert_eq!(sso, global);
+		assert_eq!(sso.clone(), sso);
+		assert_eq!(hash_builder.hash_one(&sso), hash_builder.hash_one(&global));
+		assert_eq!(hash_builder.hash_one(&sso), hash_builder.hash_one(s));
+		assert_eq!(sso.into_bytes(), s.as_bytes());
+	}
+}
@@ -3,6 +3,8 @@ use super::*;
 use ::rand::{ Rng, rngs::OsRng };
 use ::std::fmt::Debug;
 
+crate::pool_conformance_tests!(test_pool_conformance, TestPool);
+
 #[test]
 fn new() {
 	let empty = "";
@@ -0,0 +1,83 @@
+use super::*;
+use crate::pool::{ ArenaPool, GlobalPool, SlicesWrap };
+use ::std::sync::Arc;
+
+#[test]
+fn roundtrip() {
+	let pool = ArenaPool::new();
+	let strings = ["one", "two", "", "one", "ünïcödé", "two", "one"]
+		.map(|s| String::from_str_in(s, pool.clone()));
+
+	let mut encoder = StringTableEncoder::new();
+	let mut refs = Vec::new();
+	strings.iter().for_each(|s| encoder.write_ref(&mut refs, s).unwrap());
+	assert_eq!(encoder.len(), 4);
+	assert_eq!(refs, [0, 1, 2, 0, 3, 1, 0]);
+
+	let mut table = Vec::new();
+	encoder.write_table(&mut table).unwrap();
+
+	let target = ArenaPool::new();
+	let decoder = StringTableDecoder::read(&*table, target.clone()).unwrap();
+	assert_eq!(decoder.len(), 4);
+	assert_eq!(target.len(), 4);
+
+	let mut refs = &*refs;
+	let decoded = strings.iter()
+		.map(|_| decoder.read_ref(&mut refs).unwrap())
+		.collect::<Vec<_>>();
+	assert_eq!(decoded, strings);
+	assert_eq!(decoded[0].as_str().as_ptr(), decoded[3].as_str().as_ptr());
+}
+
+#[test]
+fn interned_by_pointer() {
+	let a = String::from("string table interned");
+	let b = String::from("string table other");
+
+	let mut encoder = StringTableEncoder::new();
+	assert_eq!(encoder.insert_interned(&a), 0);
+	assert_eq!(encoder.insert_interned(&b), 1);
+	assert_eq!(encoder.insert_interned(&a.clone()), 0);
+
+	// same contents from another pool shares the index
+	let other = String::from_str_in("string table interned", ArenaPool::new());
+	assert_eq!(encoder.insert(&other), 0);
+	assert_eq!(encoder.insert_str("string table other"), 1);
+	assert_eq!(encoder.len(), 2);
+}
+
+#[test]
+fn table_followed_by_data() {
+	let mut encoder = StringTableEncoder::new();
+	encoder.insert_str("followed");
+
+	let mut buf = Vec::new();
+	encoder.write_table(&mut buf).unwrap();
+	buf.extend_from_slice(b"rest");
+
+	let mut reader = &*buf;
+	let decoder = StringTableDecoder::read(&mut reader, GlobalPool).unwrap();
+	assert_eq!(decoder.get(0).unwrap(), "followed");
+	assert!(decoder.get(1).is_none());
+	assert_eq!(reader, b"rest");
+
+	let decoded = decoder.into_strings();
+	let interned = GlobalPool.raw_get(SlicesWrap(&[b"followed"])).unwrap();
+	assert!(Arc::ptr_eq(decoded[0].raw(), &interned));
+}
+
+#[test]
+fn corrupt() {
+	let read = |bytes: &[u8]| StringTableDecoder::read(bytes, GlobalPool);
+
+	assert!(matches!(read(b""), Err(SnapshotError::Truncated)));
+	assert!(matches!(read(b"\x02\x01a"), Err(SnapshotError::Truncated)));
+	assert!(matches!(read(b"\x01\x05abc"), Err(SnapshotError::Truncated)));
+	assert!(matches!(read(b"\x02\x01a\x01\xff"), Err(SnapshotError::InvalidUtf8 { index: 1 })));
+	assert!(matches!(read(b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01"), Err(SnapshotError::Malformed)));
+
+	let decoder = read(b"\x01\x01a").unwrap();
+	assert!(matches!(decoder.read_ref(&b"\x01"[..]), Err(SnapshotError::Malformed)));
+	assert!(matches!(decoder.read_ref(&b""[..]), Err(SnapshotError::Truncated)));
+}
@@ -0,0 +1,54 @@
+use super::*;
+use crate::pool::{ GlobalPool, Pool };
+
+fn raw(hash: u64, s: &str) -> Arc<SliceHashWrap> {
+	Arc::new(SliceHashWrap::new(hash, s.as_bytes().into()))
+}
+
+#[test]
+fn hit_skips_fallback() {
+	let a = raw(1, "thread cache hit");
+	let slices = SlicesWrap(&[b"thread cache ", b"hit"]);
+
+	let got = get_or_insert_with(1, &slices, || Some(Arc::clone(&a))).unwrap();
+	assert!(Arc::ptr_eq(&got, &a));
+
+	let got = get_or_insert_with(1, &slices, || unreachable!("should be cached")).unwrap();
+	assert!(Arc::ptr_eq(&got, &a));
+}
+
+#[test]
+fn collision_replaces_slot() {
+	let a = raw(2, "thread cache a");
+	let b = raw(2 + SLOTS as u64, "thread cache b");
+
+	get_or_insert_with(2, &SlicesWrap(&[b"thread cache a"]), || Some(Arc::clone(&a)));
+	get_or_insert_with(2 + SLOTS as u64, &SlicesWrap(&[b"thread cache b"]), || Some(Arc::clone(&b)));
+
+	let mut called = false;
+	get_or_insert_with(2, &SlicesWrap(&[b"thread cache a"]), || { called = true; Some(Arc::clone(&a)) });
+	assert!(called);
+}
+
+#[test]
+fn miss_is_not_cached() {
+	let slices = SlicesWrap(&[b"thread cache miss"]);
+	assert!(get_or_insert_with(3, &slices, || None).is_none());
+
+	let a = raw(3, "thread cache miss");
+	let got = get_or_insert_with(3, &slices, || Some(Arc::clone(&a))).unwrap();
+	assert!(Arc::ptr_eq(&got, &a));
+}
+
+#[test]
+fn threads_share_allocation() {
+	let a = crate::String::from("thread cache shared");
+	let b = crate::String::from("thread cache shared");
+	assert!(Arc::ptr_eq(a.raw(), b.raw()));
+
+	let other = std::thread::spawn(|| crate::String::from("thread cache shared"))
+		.join()
+		.unwrap();
+	assert!(Arc::ptr_eq(a.raw(), other.raw()));
+	assert!(Arc::ptr_eq(&GlobalPool.raw_get(SlicesWrap(&[b"thread cache shared"])).unwrap(), a.raw()));
+}
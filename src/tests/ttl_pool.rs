@@ -0,0 +1,60 @@
+use super::*;
+use crate::String;
+
+crate::pool_conformance_tests!(conformance, TtlPool::new(Duration::from_secs(60)));
+crate::pool_conformance_tests!(conformance_expired, TtlPool::new(Duration::ZERO));
+
+#[derive(Clone, Debug)]
+struct TestClock(Arc<Mutex<Instant>>);
+
+impl TestClock {
+	fn advance(&self, by: Duration) {
+		*self.0.lock() += by;
+	}
+}
+
+impl Clock for TestClock {
+	fn now(&self) -> Instant {
+		*self.0.lock()
+	}
+}
+
+#[test]
+fn sweep_expired() {
+	let clock = TestClock(Arc::new(Mutex::new(Instant::now())));
+	let pool = TtlPool::with_clock(Duration::from_secs(60), clock.clone());
+
+	let _ = String::from_str_in("old", pool.clone());
+	let old_in_use = String::from_str_in("old in use", pool.clone());
+	let _ = String::from_str_in("refreshed", pool.clone());
+
+	clock.advance(Duration::from_secs(30));
+	let _ = String::from_str_in("refreshed", pool.clone());
+	let _ = String::from_str_in("new", pool.clone());
+
+	clock.advance(Duration::from_secs(30));
+	assert_eq!(pool.len(), 4);
+	assert_eq!(pool.sweep_expired(clock.now()), 1);
+	assert!(pool.raw_get(SlicesWrap(&[b"old"])).is_none());
+	assert!(pool.raw_get(SlicesWrap(&[b"old in use"])).is_some());
+	assert!(pool.raw_get(SlicesWrap(&[b"refreshed"])).is_some());
+	assert!(pool.raw_get(SlicesWrap(&[b"new"])).is_some());
+
+	drop(old_in_use);
+	assert_eq!(pool.sweep_expired(clock.now()), 1);
+	assert_eq!(pool.len(), 2);
+
+	clock.advance(Duration::from_secs(30));
+	assert_eq!(pool.sweep_expired(clock.now()), 2);
+	assert!(pool.is_empty());
+}
+
+#[test]
+fn now_before_interned() {
+	let pool = TtlPool::new(Duration::from_secs(1));
+	let earlier = Instant::now();
+	let _ = String::from_str_in("now before interned", pool.clone());
+
+	assert_eq!(pool.sweep_expired(earlier), 0);
+	assert_eq!(pool.len(), 1);
+}